@@ -1,6 +1,7 @@
 use cohere_rust::api::chat::{ChatMessage, ChatRequest, ChatStreamRequest};
 use cohere_rust::api::GenerateModel;
 use cohere_rust::Cohere;
+use futures_util::StreamExt;
 
 #[tokio::main]
 async fn main() {
@@ -25,8 +26,8 @@ async fn main() {
     let request = ChatStreamRequest::from(request);
 
     match co.chat_stream(&request).await {
-        Ok(mut rx) => {
-            while let Some(message) = rx.recv().await {
+        Ok(mut stream) => {
+            while let Some(message) = stream.next().await {
                 match message {
                     Ok(message) => println!("Chat response: {:#?}", message),
                     Err(e) => println!("Chat error! {:#?}", e),
@@ -10,6 +10,7 @@ async fn main() {
         model: Some(EmbedModel::EnglishLightV2),
         texts: &["hello".to_string(), "goodbye".to_string()],
         truncate: Truncate::End,
+        input_type: None,
     };
 
     match co.embed(&request).await {
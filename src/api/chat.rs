@@ -1,6 +1,12 @@
-use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use serde::{de, Deserialize, Deserializer, Serialize};
 
 use super::GenerateModel;
+use crate::{
+    validation::{FieldError, Validate, Validator},
+    BilledUnits,
+};
 
 #[derive(Serialize, Default, Debug)]
 pub struct ChatRequest<'input> {
@@ -33,6 +39,20 @@ pub struct ChatRequest<'input> {
     // optional - Ensures only the top k most likely tokens are considered for generation at each step
     #[serde(skip_serializing_if = "Option::is_none")]
     pub k: Option<u64>,
+    /// optional - Documents the model should ground its answer on, citing them in the response.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub documents: Option<&'input Vec<ChatDocument>>,
+    /// optional - Whether citations are generated accurately or fast. Defaults to `accurate`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub citation_quality: Option<CitationQuality>,
+}
+
+impl<'input> Validate for ChatRequest<'input> {
+    fn validate(&self) -> Result<(), Vec<FieldError>> {
+        Validator::new()
+            .non_negative("temperature", self.temperature)
+            .finish()
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, PartialEq)]
@@ -51,6 +71,12 @@ pub struct ChatStreamRequest<'input> {
     stream: bool,
 }
 
+impl<'input> Validate for ChatStreamRequest<'input> {
+    fn validate(&self) -> Result<(), Vec<FieldError>> {
+        self.request.validate()
+    }
+}
+
 impl<'input> From<ChatRequest<'input>> for ChatStreamRequest<'input> {
     fn from(request: ChatRequest<'input>) -> Self {
         Self {
@@ -70,7 +96,7 @@ pub enum PromptTruncation {
     Off,
 }
 
-#[derive(strum_macros::Display, Serialize, Debug)]
+#[derive(strum_macros::Display, Serialize, Debug, Clone, Copy, PartialEq)]
 pub enum CitationQuality {
     #[strum(serialize = "accurate")]
     #[serde(rename = "accurate")]
@@ -80,27 +106,223 @@ pub enum CitationQuality {
     Fast,
 }
 
-#[derive(Deserialize, Debug, PartialEq)]
+#[derive(Deserialize, Default, Debug, Clone, PartialEq)]
 pub struct ChatResponse {
     pub generation_id: String,
     pub response_id: String,
     pub text: String,
+    /// Why the generation stopped, e.g. `COMPLETE` or `MAX_TOKENS`.
+    pub finish_reason: Option<String>,
+    /// The spans of the text grounded on documents.
+    pub citations: Option<Vec<ChatCitation>>,
+    /// The documents the citations refer to.
+    pub documents: Option<Vec<ChatDocument>>,
+    /// The tools the model wants the caller to run, when tools were given.
+    pub tool_calls: Option<Vec<ToolCall>>,
+    /// The usage of the chat.
+    pub meta: Option<ChatMeta>,
+}
+
+impl ChatResponse {
+    /// The documents a citation of the response refers to.
+    pub fn cited_documents<'a>(&'a self, citation: &'a ChatCitation) -> Vec<&'a ChatDocument> {
+        self.documents
+            .iter()
+            .flatten()
+            .filter(|document| {
+                document
+                    .id
+                    .as_ref()
+                    .is_some_and(|id| citation.document_ids.contains(id))
+            })
+            .collect()
+    }
+}
+
+/// The usage of a chat, see [`ChatResponse::meta`].
+#[derive(Deserialize, Default, Debug, Clone, PartialEq)]
+pub struct ChatMeta {
+    /// The units the chat was billed for.
+    pub billed_units: Option<BilledUnits>,
+    /// The tokens the chat actually used, billed or not.
+    pub tokens: Option<ChatTokens>,
+}
+
+#[derive(Deserialize, Default, Debug, Clone, PartialEq)]
+pub struct ChatTokens {
+    pub input_tokens: Option<f64>,
+    pub output_tokens: Option<f64>,
+}
+
+/// A search query generated by the model to look for relevant documents.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct ChatSearchQuery {
+    pub text: String,
+    pub generation_id: String,
+}
+
+/// The connector a search query was run against.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct ChatSearchResultConnector {
+    pub id: String,
 }
 
-#[derive(Deserialize, Debug, PartialEq)]
-#[serde(tag = "event_type")]
+/// The documents retrieved by a search query.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct ChatSearchResult {
+    pub search_query: Option<ChatSearchQuery>,
+    pub connector: ChatSearchResultConnector,
+    /// Identifiers of the documents found, see [`ChatDocument`].
+    #[serde(default)]
+    pub document_ids: Vec<String>,
+    /// optional - Why the search failed, when it did.
+    pub error_message: Option<String>,
+    /// optional - Whether the chat continued after the search failed.
+    pub continue_on_failure: Option<bool>,
+}
+
+/// A document the model can ground its answer on.
+#[derive(Serialize, Deserialize, Default, Debug, Clone, PartialEq)]
+pub struct ChatDocument {
+    /// optional - Identifies the document in citations. Generated by the API when not set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub snippet: Option<String>,
+    /// Any other field of the document, e.g. `url`.
+    #[serde(flatten)]
+    pub fields: HashMap<String, serde_json::Value>,
+}
+
+/// A span of the generated text, grounded on some documents.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct ChatCitation {
+    /// Offset of the first character of the span in the text.
+    pub start: u64,
+    /// Offset of the character after the span in the text.
+    pub end: u64,
+    pub text: String,
+    pub document_ids: Vec<String>,
+}
+
+/// A call of a tool the model wants the caller to make.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct ToolCall {
+    pub name: String,
+    #[serde(default)]
+    pub parameters: HashMap<String, serde_json::Value>,
+}
+
+/// A part of a tool call, as it is being generated.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct ToolCallDelta {
+    /// Index of the tool call the part belongs to.
+    pub index: Option<u64>,
+    pub name: Option<String>,
+    /// A part of the JSON encoded parameters of the call.
+    pub parameters: Option<String>,
+    pub text: Option<String>,
+}
+
+/// Event types of the [`StreamEvent`] variants, any other one is [`StreamEvent::Unknown`].
+const EVENT_TYPES: [&str; 8] = [
+    "stream-start",
+    "search-queries-generation",
+    "search-results",
+    "text-generation",
+    "citation-generation",
+    "tool-calls-generation",
+    "tool-calls-chunk",
+    "stream-end",
+];
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(remote = "Self", tag = "event_type")]
 pub enum StreamEvent {
     #[serde(rename = "stream-start")]
     Start {
         generation_id: String,
         is_finished: bool,
     },
+    #[serde(rename = "search-queries-generation")]
+    SearchQueriesGeneration {
+        is_finished: bool,
+        search_queries: Vec<ChatSearchQuery>,
+    },
+    #[serde(rename = "search-results")]
+    SearchResults {
+        is_finished: bool,
+        search_results: Option<Vec<ChatSearchResult>>,
+        documents: Option<Vec<ChatDocument>>,
+    },
     #[serde(rename = "text-generation")]
     TextGeneration { is_finished: bool, text: String },
+    #[serde(rename = "citation-generation")]
+    CitationGeneration {
+        is_finished: bool,
+        citations: Vec<ChatCitation>,
+    },
+    #[serde(rename = "tool-calls-generation")]
+    ToolCallsGeneration {
+        is_finished: bool,
+        /// optional - The text generated before the tool calls.
+        text: Option<String>,
+        tool_calls: Vec<ToolCall>,
+    },
+    #[serde(rename = "tool-calls-chunk")]
+    ToolCallsChunk {
+        is_finished: bool,
+        tool_call_delta: ToolCallDelta,
+        text: Option<String>,
+    },
     #[serde(rename = "stream-end")]
     End {
         finish_reason: String,
         is_finished: bool,
         response: ChatResponse,
     },
+    /// An event this version of the crate does not know about, e.g. one added to the API since.
+    #[serde(skip)]
+    Unknown {
+        event_type: String,
+        /// The whole event, as sent by the API.
+        raw: serde_json::Value,
+    },
+}
+
+impl StreamEvent {
+    /// The `event_type` of the event, e.g. `"text-generation"`.
+    pub fn event_type(&self) -> &str {
+        match self {
+            StreamEvent::Start { .. } => "stream-start",
+            StreamEvent::SearchQueriesGeneration { .. } => "search-queries-generation",
+            StreamEvent::SearchResults { .. } => "search-results",
+            StreamEvent::TextGeneration { .. } => "text-generation",
+            StreamEvent::CitationGeneration { .. } => "citation-generation",
+            StreamEvent::ToolCallsGeneration { .. } => "tool-calls-generation",
+            StreamEvent::ToolCallsChunk { .. } => "tool-calls-chunk",
+            StreamEvent::End { .. } => "stream-end",
+            StreamEvent::Unknown { event_type, .. } => event_type,
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for StreamEvent {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let raw = serde_json::Value::deserialize(deserializer)?;
+        let event_type = raw
+            .get("event_type")
+            .and_then(serde_json::Value::as_str)
+            .ok_or_else(|| de::Error::missing_field("event_type"))?;
+
+        if !EVENT_TYPES.contains(&event_type) {
+            return Ok(StreamEvent::Unknown {
+                event_type: event_type.to_string(),
+                raw,
+            });
+        }
+        StreamEvent::deserialize(raw).map_err(de::Error::custom)
+    }
 }
@@ -3,6 +3,7 @@ use std::collections::HashMap;
 use serde::{Deserialize, Serialize};
 
 use super::{EmbedModel, Truncate};
+use crate::validation::{FieldError, Validate, Validator};
 
 #[derive(Serialize, Default, Debug)]
 pub struct ClassifyRequest<'input> {
@@ -20,6 +21,12 @@ pub struct ClassifyRequest<'input> {
     pub truncate: Option<Truncate>,
 }
 
+impl<'input> Validate for ClassifyRequest<'input> {
+    fn validate(&self) -> Result<(), Vec<FieldError>> {
+        Validator::new().not_empty("inputs", self.inputs).finish()
+    }
+}
+
 #[derive(Serialize, Debug)]
 pub struct ClassifyExample<'input> {
     /// The text of the example.
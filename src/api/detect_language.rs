@@ -1,11 +1,19 @@
 use serde::{Deserialize, Serialize};
 
+use crate::validation::{FieldError, Validate, Validator};
+
 #[derive(Serialize, Debug)]
 pub struct DetectLanguageRequest<'input> {
     /// List of detected languages, one per text
     pub texts: &'input [String],
 }
 
+impl<'input> Validate for DetectLanguageRequest<'input> {
+    fn validate(&self) -> Result<(), Vec<FieldError>> {
+        Validator::new().not_empty("texts", self.texts).finish()
+    }
+}
+
 #[derive(Deserialize, Debug)]
 pub(crate) struct DetectLanguageResponse {
     /// List of detected languages, one per text
@@ -1,6 +1,7 @@
 use serde::{Deserialize, Serialize};
 
 use super::GenerateModel;
+use crate::validation::{FieldError, Validate};
 
 #[derive(Serialize, Debug)]
 pub struct DetokenizeRequest<'input> {
@@ -11,6 +12,12 @@ pub struct DetokenizeRequest<'input> {
     pub model: Option<GenerateModel>,
 }
 
+impl<'input> Validate for DetokenizeRequest<'input> {
+    fn validate(&self) -> Result<(), Vec<FieldError>> {
+        Ok(())
+    }
+}
+
 #[derive(Deserialize, Debug)]
 pub(crate) struct DetokenizeResponse {
     /// The text representation of the tokens
@@ -1,8 +1,9 @@
 use serde::{Deserialize, Serialize};
 
 use super::{EmbedModel, Truncate};
+use crate::validation::{FieldError, Validate, Validator};
 
-#[derive(Serialize, Debug)]
+#[derive(Serialize, Debug, Clone)]
 pub struct EmbedRequest<'input> {
     /// An optional string representing the model you'd like to use.
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -11,6 +12,35 @@ pub struct EmbedRequest<'input> {
     pub texts: &'input [String],
     /// Specify how the API will handle inputs longer than the maximum token length.
     pub truncate: Truncate,
+    /// optional - The purpose of the embeddings, required by v3 models.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub input_type: Option<EmbedInputType>,
+}
+
+#[derive(strum_macros::Display, Serialize, Debug, Clone, Copy, PartialEq)]
+pub enum EmbedInputType {
+    /// Embeddings of documents stored in a vector database for search use-cases.
+    #[strum(serialize = "search_document")]
+    #[serde(rename = "search_document")]
+    SearchDocument,
+    /// Embeddings of search queries run against a vector database to find relevant documents.
+    #[strum(serialize = "search_query")]
+    #[serde(rename = "search_query")]
+    SearchQuery,
+    /// Embeddings passed through a text classifier.
+    #[strum(serialize = "classification")]
+    #[serde(rename = "classification")]
+    Classification,
+    /// Embeddings run through a clustering algorithm.
+    #[strum(serialize = "clustering")]
+    #[serde(rename = "clustering")]
+    Clustering,
+}
+
+impl<'input> Validate for EmbedRequest<'input> {
+    fn validate(&self) -> Result<(), Vec<FieldError>> {
+        Validator::new().not_empty("texts", self.texts).finish()
+    }
 }
 
 #[derive(Deserialize, Debug)]
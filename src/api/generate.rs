@@ -2,6 +2,7 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
 use super::{GenerateModel, Truncate};
+use crate::validation::{FieldError, Validate, Validator};
 
 #[derive(Serialize, Default, Debug)]
 pub struct GenerateRequest<'input> {
@@ -70,6 +71,36 @@ pub struct GenerateRequest<'input> {
     pub truncate: Option<Truncate>,
 }
 
+impl<'input> Validate for GenerateRequest<'input> {
+    fn validate(&self) -> Result<(), Vec<FieldError>> {
+        let mut validator = Validator::new();
+        validator
+            .non_negative("temperature", self.temperature)
+            .range(
+                "num_generations",
+                self.num_generations.map(f64::from),
+                1.0,
+                5.0,
+            )
+            .range("p", self.p, 0.0, 1.0)
+            .range("frequency_penalty", self.frequency_penalty, 0.0, 1.0)
+            .range("presence_penalty", self.presence_penalty, 0.0, 1.0);
+        if let Some(logit_bias) = &self.logit_bias {
+            let mut tokens: Vec<_> = logit_bias.iter().collect();
+            tokens.sort_by_key(|(token, _)| **token);
+            for (token, bias) in tokens {
+                validator.range(
+                    format!("logit_bias[{token}]"),
+                    Some(f64::from(*bias)),
+                    -10.0,
+                    10.0,
+                );
+            }
+        }
+        validator.finish()
+    }
+}
+
 #[derive(strum_macros::Display, Serialize, Debug)]
 pub enum ReturnLikelihoods {
     #[strum(serialize = "GENERATION")]
@@ -10,7 +10,7 @@ pub mod rerank;
 pub mod summarize;
 pub mod tokenize;
 
-#[derive(strum_macros::Display, Serialize, Debug)]
+#[derive(strum_macros::Display, Serialize, Debug, Clone)]
 pub enum Truncate {
     #[strum(serialize = "NONE")]
     #[serde(rename = "NONE")]
@@ -23,7 +23,7 @@ pub enum Truncate {
     End,
 }
 
-#[derive(strum_macros::Display, Serialize, Debug)]
+#[derive(strum_macros::Display, Serialize, Debug, Clone)]
 pub enum EmbedModel {
     /// A model that allows for text to be classified or turned into embeddings. English only.
     /// Embedding vector of size 1024.
@@ -1,5 +1,7 @@
 use serde::{Deserialize, Serialize};
 
+use crate::validation::{FieldError, Validate, Validator};
+
 #[derive(Serialize, Debug, Default)]
 pub struct RerankRequest<'input> {
     /// The search query.
@@ -14,6 +16,14 @@ pub struct RerankRequest<'input> {
     pub max_chunks_per_doc: Option<u64>,
 }
 
+impl<'input> Validate for RerankRequest<'input> {
+    fn validate(&self) -> Result<(), Vec<FieldError>> {
+        Validator::new()
+            .not_empty("documents", self.documents)
+            .finish()
+    }
+}
+
 #[derive(strum_macros::Display, Serialize, Debug, Default)]
 pub enum RerankModel {
     /// A model that allows for re-ranking English Language documents and semi-structured data (JSON).
@@ -1,6 +1,7 @@
 use serde::{Deserialize, Serialize};
 
 use super::GenerateModel;
+use crate::validation::{FieldError, Validate, Validator};
 
 #[derive(Serialize, Default, Debug)]
 pub struct SummarizeRequest<'input> {
@@ -32,6 +33,14 @@ pub struct SummarizeRequest<'input> {
     pub model: Option<GenerateModel>,
 }
 
+impl<'input> Validate for SummarizeRequest<'input> {
+    fn validate(&self) -> Result<(), Vec<FieldError>> {
+        Validator::new()
+            .range("temperature", self.temperature, 0.0, 5.0)
+            .finish()
+    }
+}
+
 #[derive(strum_macros::Display, Serialize, Debug)]
 pub enum SummarizeLength {
     #[strum(serialize = "short")]
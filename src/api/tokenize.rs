@@ -1,6 +1,7 @@
 use serde::{Deserialize, Serialize};
 
 use super::GenerateModel;
+use crate::validation::{FieldError, Validate};
 
 #[derive(Serialize, Debug)]
 pub struct TokenizeRequest<'input> {
@@ -11,6 +12,12 @@ pub struct TokenizeRequest<'input> {
     pub model: Option<GenerateModel>,
 }
 
+impl<'input> Validate for TokenizeRequest<'input> {
+    fn validate(&self) -> Result<(), Vec<FieldError>> {
+        Ok(())
+    }
+}
+
 #[derive(Deserialize, Debug)]
 pub struct TokenizeResponse {
     /// The tokens
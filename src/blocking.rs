@@ -0,0 +1,280 @@
+//! A blocking client, for callers that do not run an async runtime.
+//!
+//! The methods mirror the ones of the async [`crate::Cohere`] client.
+//! They must not be called from within an async runtime.
+
+use std::sync::Arc;
+
+use tokio::runtime::Runtime;
+
+use crate::{
+    api::{
+        chat::{ChatRequest, ChatResponse, ChatStreamRequest, StreamEvent},
+        classify::{Classification, ClassifyRequest},
+        detect_language::{DetectLanguageRequest, DetectLanguageResult},
+        detokenize::DetokenizeRequest,
+        embed::EmbedRequest,
+        generate::{GenerateRequest, Generation},
+        rerank::{RerankRequest, RerankResult},
+        summarize::SummarizeRequest,
+        tokenize::{TokenizeRequest, TokenizeResponse},
+    },
+    CacheStats, ChatStream, CohereApiError, CohereConfigError, CohereStreamError, EndpointHealth,
+    RateLimitStats, RequestOptions, WithMeta,
+};
+
+/// Blocking version of the [`crate::Cohere`] client.
+#[derive(Clone)]
+pub struct Cohere {
+    inner: crate::Cohere,
+    runtime: Arc<Runtime>,
+}
+
+/// Iterator over the events of a streamed chat, see [`Cohere::chat_stream`].
+pub struct ChatStreamIter<'a> {
+    runtime: &'a Runtime,
+    stream: ChatStream,
+}
+
+impl Iterator for ChatStreamIter<'_> {
+    type Item = Result<StreamEvent, CohereStreamError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.runtime.block_on(self.stream.recv())
+    }
+}
+
+impl Cohere {
+    /// Creates a client for the given API url and key.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the url or key are invalid, see [`Cohere::try_new`] for a fallible version.
+    pub fn new<U: Into<String>, K: Into<String>>(api_url: U, api_key: K) -> Self {
+        Self::try_new(api_url, api_key).unwrap_or_else(|error| panic!("{error}"))
+    }
+
+    /// Creates a client for the given API url and key, returning an error if they are invalid.
+    pub fn try_new<U: Into<String>, K: Into<String>>(
+        api_url: U,
+        api_key: K,
+    ) -> Result<Self, CohereConfigError> {
+        Self::from_async(crate::Cohere::try_new(api_url, api_key)?)
+    }
+
+    /// Creates a client using the API key from the `CO_API_KEY` env variable.
+    pub fn from_env() -> Result<Self, CohereConfigError> {
+        Self::from_async(crate::Cohere::from_env()?)
+    }
+
+    /// Wraps an async client, e.g. one configured with [`crate::CohereBuilder`].
+    pub fn from_async(client: crate::Cohere) -> Result<Self, CohereConfigError> {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .map_err(CohereConfigError::Runtime)?;
+
+        Ok(Self {
+            inner: client,
+            runtime: Arc::new(runtime),
+        })
+    }
+
+    /// Returns a client that applies the given options to all of its calls.
+    /// It shares the configuration and state of this client.
+    pub fn with_options(&self, options: RequestOptions) -> Cohere {
+        Cohere {
+            inner: self.inner.with_options(options),
+            runtime: self.runtime.clone(),
+        }
+    }
+
+    /// Returns how much calls to the given route have been delayed by the client-side rate limiter,
+    /// or `None` if the route is not rate limited.
+    pub fn rate_limit_stats(&self, route: &str) -> Option<RateLimitStats> {
+        self.inner.rate_limit_stats(route)
+    }
+
+    /// Returns the hits and misses of the response cache, or `None` if it is not enabled.
+    pub fn cache_stats(&self) -> Option<CacheStats> {
+        self.inner.cache_stats()
+    }
+
+    /// Returns the API endpoints of the client with the state of their circuit breaker.
+    pub fn endpoints(&self) -> Vec<EndpointHealth> {
+        self.inner.endpoints()
+    }
+
+    /// Probes the endpoints skipped by the circuit breaker, see [`crate::Cohere::probe_endpoints`].
+    pub fn probe_endpoints(&self) -> Vec<EndpointHealth> {
+        self.runtime.block_on(self.inner.probe_endpoints())
+    }
+
+    /// Verify that the Cohere API key being used is valid
+    pub fn check_api_key(&self) -> Result<(), CohereApiError> {
+        self.runtime.block_on(self.inner.check_api_key())
+    }
+
+    /// Generates realistic text conditioned on a given input.
+    pub fn generate<'input>(
+        &self,
+        request: &GenerateRequest<'input>,
+    ) -> Result<Vec<Generation>, CohereApiError> {
+        self.runtime.block_on(self.inner.generate(request))
+    }
+
+    /// Same as [`Cohere::generate`], also returning the response metadata.
+    pub fn generate_with_meta<'input>(
+        &self,
+        request: &GenerateRequest<'input>,
+    ) -> Result<WithMeta<Vec<Generation>>, CohereApiError> {
+        self.runtime
+            .block_on(self.inner.generate_with_meta(request))
+    }
+
+    /// Chat with Cohere's LLM
+    pub fn chat<'input>(
+        &self,
+        request: &ChatRequest<'input>,
+    ) -> Result<ChatResponse, CohereApiError> {
+        self.runtime.block_on(self.inner.chat(request))
+    }
+
+    /// Same as [`Cohere::chat`], also returning the response metadata.
+    pub fn chat_with_meta<'input>(
+        &self,
+        request: &ChatRequest<'input>,
+    ) -> Result<WithMeta<ChatResponse>, CohereApiError> {
+        self.runtime.block_on(self.inner.chat_with_meta(request))
+    }
+
+    /// Chat with Cohere's LLM, returning an iterator over the streamed events.
+    pub fn chat_stream<'input>(
+        &self,
+        request: &ChatStreamRequest<'input>,
+    ) -> Result<ChatStreamIter<'_>, CohereApiError> {
+        let stream = self.runtime.block_on(self.inner.chat_stream(request))?;
+
+        Ok(ChatStreamIter {
+            runtime: &self.runtime,
+            stream,
+        })
+    }
+
+    /// Returns text embeddings.
+    pub fn embed<'input>(
+        &self,
+        request: &EmbedRequest<'input>,
+    ) -> Result<Vec<Vec<f64>>, CohereApiError> {
+        self.runtime.block_on(self.inner.embed(request))
+    }
+
+    /// Same as [`Cohere::embed`], also returning the response metadata.
+    pub fn embed_with_meta<'input>(
+        &self,
+        request: &EmbedRequest<'input>,
+    ) -> Result<WithMeta<Vec<Vec<f64>>>, CohereApiError> {
+        self.runtime.block_on(self.inner.embed_with_meta(request))
+    }
+
+    /// Makes a prediction about which label fits the specified text inputs best.
+    pub fn classify<'input>(
+        &self,
+        request: &ClassifyRequest<'input>,
+    ) -> Result<Vec<Classification>, CohereApiError> {
+        self.runtime.block_on(self.inner.classify(request))
+    }
+
+    /// Same as [`Cohere::classify`], also returning the response metadata.
+    pub fn classify_with_meta<'input>(
+        &self,
+        request: &ClassifyRequest<'input>,
+    ) -> Result<WithMeta<Vec<Classification>>, CohereApiError> {
+        self.runtime
+            .block_on(self.inner.classify_with_meta(request))
+    }
+
+    /// Generates a summary in English for a given text.
+    pub fn summarize<'input>(
+        &self,
+        request: &SummarizeRequest<'input>,
+    ) -> Result<String, CohereApiError> {
+        self.runtime.block_on(self.inner.summarize(request))
+    }
+
+    /// Same as [`Cohere::summarize`], also returning the response metadata.
+    pub fn summarize_with_meta<'input>(
+        &self,
+        request: &SummarizeRequest<'input>,
+    ) -> Result<WithMeta<String>, CohereApiError> {
+        self.runtime
+            .block_on(self.inner.summarize_with_meta(request))
+    }
+
+    /// Splits input text into smaller units called tokens using byte-pair encoding (BPE).
+    pub fn tokenize<'input>(
+        &self,
+        request: &TokenizeRequest<'input>,
+    ) -> Result<TokenizeResponse, CohereApiError> {
+        self.runtime.block_on(self.inner.tokenize(request))
+    }
+
+    /// Same as [`Cohere::tokenize`], also returning the response metadata.
+    pub fn tokenize_with_meta<'input>(
+        &self,
+        request: &TokenizeRequest<'input>,
+    ) -> Result<WithMeta<TokenizeResponse>, CohereApiError> {
+        self.runtime
+            .block_on(self.inner.tokenize_with_meta(request))
+    }
+
+    /// Takes tokens using byte-pair encoding and returns their text representation.
+    pub fn detokenize<'input>(
+        &self,
+        request: &DetokenizeRequest<'input>,
+    ) -> Result<String, CohereApiError> {
+        self.runtime.block_on(self.inner.detokenize(request))
+    }
+
+    /// Same as [`Cohere::detokenize`], also returning the response metadata.
+    pub fn detokenize_with_meta<'input>(
+        &self,
+        request: &DetokenizeRequest<'input>,
+    ) -> Result<WithMeta<String>, CohereApiError> {
+        self.runtime
+            .block_on(self.inner.detokenize_with_meta(request))
+    }
+
+    /// Identifies which language each of the provided texts is written in
+    pub fn detect_language<'input>(
+        &self,
+        request: &DetectLanguageRequest<'input>,
+    ) -> Result<Vec<DetectLanguageResult>, CohereApiError> {
+        self.runtime.block_on(self.inner.detect_language(request))
+    }
+
+    /// Same as [`Cohere::detect_language`], also returning the response metadata.
+    pub fn detect_language_with_meta<'input>(
+        &self,
+        request: &DetectLanguageRequest<'input>,
+    ) -> Result<WithMeta<Vec<DetectLanguageResult>>, CohereApiError> {
+        self.runtime
+            .block_on(self.inner.detect_language_with_meta(request))
+    }
+
+    /// Takes a query plus an list of texts and return an ordered array with each text assigned a relevance score.
+    pub fn rerank<'input>(
+        &self,
+        request: &RerankRequest<'input>,
+    ) -> Result<Vec<RerankResult>, CohereApiError> {
+        self.runtime.block_on(self.inner.rerank(request))
+    }
+
+    /// Same as [`Cohere::rerank`], also returning the response metadata.
+    pub fn rerank_with_meta<'input>(
+        &self,
+        request: &RerankRequest<'input>,
+    ) -> Result<WithMeta<Vec<RerankResult>>, CohereApiError> {
+        self.runtime.block_on(self.inner.rerank_with_meta(request))
+    }
+}
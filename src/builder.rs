@@ -0,0 +1,368 @@
+use std::{collections::HashMap, path::PathBuf, sync::Arc, time::Duration};
+
+use reqwest::{header, ClientBuilder, Url};
+
+use crate::{
+    cache::ResponseCache,
+    cassette::{RecordingTransport, ReplayTransport},
+    credentials::bearer,
+    failover::Endpoints,
+    rate_limit::RateLimiter,
+    ApiWarning, CacheConfig, Cohere, CohereConfigError, CredentialProvider, EmbeddingStore,
+    FailoverPolicy, Metrics, Middleware, RateLimit, RequestOptions, ReqwestTransport, RetryPolicy,
+    StaticCredentials, Transport, WarningHandler, COHERE_API_BASE_URL, COHERE_API_TIMEOUT,
+    COHERE_API_V1,
+};
+
+const COHERE_REQUEST_SOURCE: &str = "rust-sdk";
+
+enum CassetteMode {
+    Record(PathBuf),
+    Replay(PathBuf),
+}
+
+/// Builder used to configure a [`Cohere`] client.
+///
+/// ```no_run
+/// use std::time::Duration;
+/// use cohere_rust::Cohere;
+///
+/// let co = Cohere::builder()
+///     .api_key("my-api-key")
+///     .timeout(Duration::from_secs(30))
+///     .connect_timeout(Duration::from_secs(5))
+///     .request_source("my-service")
+///     .build();
+/// ```
+#[derive(Default)]
+pub struct CohereBuilder {
+    api_urls: Vec<String>,
+    failover_policy: Option<FailoverPolicy>,
+    api_key: Option<String>,
+    credentials: Option<Arc<dyn CredentialProvider>>,
+    timeout: Option<Duration>,
+    connect_timeout: Option<Duration>,
+    proxy: Option<reqwest::Proxy>,
+    pool_max_idle_per_host: Option<usize>,
+    pool_idle_timeout: Option<Duration>,
+    default_headers: header::HeaderMap,
+    request_source: Option<String>,
+    http_client: Option<reqwest::Client>,
+    transport: Option<Arc<dyn Transport>>,
+    cassette: Option<CassetteMode>,
+    retry_policy: Option<RetryPolicy>,
+    rate_limits: HashMap<String, RateLimit>,
+    warning_handler: Option<WarningHandler>,
+    middlewares: Vec<Arc<dyn Middleware>>,
+    metrics: Option<Arc<dyn Metrics>>,
+    cache: Option<CacheConfig>,
+    embedding_store: Option<Arc<EmbeddingStore>>,
+}
+
+impl CohereBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Base URL of the Cohere API. Defaults to `https://api.cohere.ai/v1`.
+    pub fn api_url<U: Into<String>>(mut self, api_url: U) -> Self {
+        self.api_urls = vec![api_url.into()];
+        self
+    }
+
+    /// Base URLs of several deployments of the Cohere API, in order of preference.
+    ///
+    /// Requests go to the first available one and fail over to the next on connection errors
+    /// and server errors. Endpoints failing repeatedly are skipped for a while, see [`FailoverPolicy`].
+    pub fn api_urls<I: IntoIterator<Item = U>, U: Into<String>>(mut self, api_urls: I) -> Self {
+        self.api_urls = api_urls.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Circuit breaker applied to the endpoints set with [`CohereBuilder::api_urls`].
+    pub fn failover_policy(mut self, policy: FailoverPolicy) -> Self {
+        self.failover_policy = Some(policy);
+        self
+    }
+
+    /// API key used to authenticate requests. Defaults to the `CO_API_KEY` env variable.
+    pub fn api_key<K: Into<String>>(mut self, api_key: K) -> Self {
+        self.api_key = Some(api_key.into());
+        self
+    }
+
+    /// Provider queried for the API key of every request, replacing [`CohereBuilder::api_key`].
+    /// Use it to rotate keys without rebuilding the client.
+    pub fn credentials<C: CredentialProvider + 'static>(mut self, credentials: C) -> Self {
+        self.credentials = Some(Arc::new(credentials));
+        self
+    }
+
+    /// Total timeout applied to every request. Defaults to 120 seconds.
+    /// Ignored when a custom HTTP client is supplied with [`CohereBuilder::http_client`].
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Timeout for establishing a connection.
+    /// Ignored when a custom HTTP client is supplied with [`CohereBuilder::http_client`].
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// Route all requests through the given proxy.
+    /// Ignored when a custom HTTP client is supplied with [`CohereBuilder::http_client`].
+    pub fn proxy(mut self, proxy: reqwest::Proxy) -> Self {
+        self.proxy = Some(proxy);
+        self
+    }
+
+    /// Maximum number of idle connections kept per host.
+    /// Ignored when a custom HTTP client is supplied with [`CohereBuilder::http_client`].
+    pub fn pool_max_idle_per_host(mut self, max: usize) -> Self {
+        self.pool_max_idle_per_host = Some(max);
+        self
+    }
+
+    /// How long idle connections are kept in the pool.
+    /// Ignored when a custom HTTP client is supplied with [`CohereBuilder::http_client`].
+    pub fn pool_idle_timeout(mut self, timeout: Duration) -> Self {
+        self.pool_idle_timeout = Some(timeout);
+        self
+    }
+
+    /// Extra header sent with every request.
+    /// Headers set by the SDK itself (authorization, content type) take precedence.
+    pub fn default_header(mut self, name: header::HeaderName, value: header::HeaderValue) -> Self {
+        self.default_headers.insert(name, value);
+        self
+    }
+
+    /// Value of the `Request-Source` header. Defaults to `rust-sdk`.
+    pub fn request_source<S: Into<String>>(mut self, source: S) -> Self {
+        self.request_source = Some(source.into());
+        self
+    }
+
+    /// Use an already configured HTTP client instead of building a new one.
+    /// The SDK headers are still attached to every request.
+    pub fn http_client(mut self, client: reqwest::Client) -> Self {
+        self.http_client = Some(client);
+        self
+    }
+
+    /// Send requests through a custom [`Transport`] instead of the default reqwest based one.
+    /// Options related to the HTTP client (timeouts, proxy, pool) are ignored when set.
+    pub fn transport<T: Transport + 'static>(mut self, transport: T) -> Self {
+        self.transport = Some(Arc::new(transport));
+        self
+    }
+
+    /// Save every request/response pair to the cassette file at `path`, with the API key redacted.
+    /// See [`RecordingTransport`].
+    pub fn record_to<P: Into<PathBuf>>(mut self, path: P) -> Self {
+        self.cassette = Some(CassetteMode::Record(path.into()));
+        self
+    }
+
+    /// Answer requests offline from the cassette file at `path`, failing on unmatched requests.
+    /// See [`ReplayTransport`].
+    pub fn replay_from<P: Into<PathBuf>>(mut self, path: P) -> Self {
+        self.cassette = Some(CassetteMode::Replay(path.into()));
+        self
+    }
+
+    /// Retry failed requests according to the given policy. Requests are not retried by default.
+    pub fn retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.retry_policy = Some(policy);
+        self
+    }
+
+    /// Limit how fast requests are sent to a route, e.g. `"embed"` or `"chat"`.
+    /// Calls above the limit wait for capacity instead of failing. A limit of zero requests or
+    /// over an empty period fails [`CohereBuilder::try_build`].
+    pub fn rate_limit<R: Into<String>>(mut self, route: R, limit: RateLimit) -> Self {
+        self.rate_limits.insert(route.into(), limit);
+        self
+    }
+
+    /// Called with every warning returned by the API, such as model deprecation notices, and
+    /// with the warnings raised by the client itself, such as a failed write to the
+    /// [`EmbeddingStore`] ([`WarningKind::Storage`](crate::WarningKind::Storage)). When no handler is registered, warnings are logged with `tracing` if the `tracing` feature
+    /// is enabled, and ignored otherwise.
+    pub fn on_warning<F>(mut self, handler: F) -> Self
+    where
+        F: Fn(&ApiWarning) + Send + Sync + 'static,
+    {
+        self.warning_handler = Some(Arc::new(handler));
+        self
+    }
+
+    /// Appends a middleware to the chain run around every request.
+    /// See [`Middleware`] for the order in which they run.
+    pub fn middleware<M: Middleware + 'static>(mut self, middleware: M) -> Self {
+        self.middlewares.push(Arc::new(middleware));
+        self
+    }
+
+    /// Reports every call to the given metrics sink, e.g. an `Arc<InMemoryMetrics>` kept to read
+    /// the totals later.
+    pub fn metrics<M: Metrics + 'static>(mut self, metrics: M) -> Self {
+        self.metrics = Some(Arc::new(metrics));
+        self
+    }
+
+    /// Caches the responses of `embed`, `tokenize`, `detokenize` and `detect_language` in memory.
+    /// Embeddings are cached per text, so that only the uncached texts of a batch are sent.
+    pub fn cache(mut self, config: CacheConfig) -> Self {
+        self.cache = Some(config);
+        self
+    }
+
+    /// Persists embeddings to the given store, so that texts already embedded by a previous run
+    /// are not sent again. Only requests with an explicit model use the store. Stores can be
+    /// shared between clients, e.g. with an `Arc`.
+    pub fn embedding_store<S: Into<Arc<EmbeddingStore>>>(mut self, store: S) -> Self {
+        self.embedding_store = Some(store.into());
+        self
+    }
+
+    /// Builds the client.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the configuration is invalid, see [`CohereBuilder::try_build`] for a fallible version.
+    pub fn build(self) -> Cohere {
+        self.try_build().unwrap_or_else(|error| panic!("{error}"))
+    }
+
+    /// Builds the client, returning an error if the configuration is invalid.
+    pub fn try_build(self) -> Result<Cohere, CohereConfigError> {
+        for (route, limit) in &self.rate_limits {
+            let reason = match (limit.requests, limit.per) {
+                (0, _) => "must allow at least one request",
+                (_, Duration::ZERO) => "must be over a non-empty period",
+                _ => continue,
+            };
+            return Err(CohereConfigError::InvalidRateLimit {
+                route: route.clone(),
+                reason: reason.to_string(),
+            });
+        }
+
+        let api_urls = match self.api_urls.is_empty() {
+            true => vec![format!("{COHERE_API_BASE_URL}/{COHERE_API_V1}")],
+            false => self.api_urls,
+        };
+        let endpoints = Endpoints::new(
+            api_urls
+                .iter()
+                .map(|api_url| parse_api_url(api_url))
+                .collect::<Result<_, _>>()?,
+            self.failover_policy.unwrap_or_default(),
+        );
+        let credentials: Arc<dyn CredentialProvider> = match (self.credentials, self.api_key) {
+            (Some(credentials), _) => credentials,
+            (None, api_key) => {
+                let api_key = match api_key {
+                    Some(api_key) => api_key,
+                    None => {
+                        std::env::var("CO_API_KEY").map_err(|_| CohereConfigError::MissingApiKey)?
+                    }
+                };
+                bearer(&api_key).map_err(CohereConfigError::InvalidApiKey)?;
+                Arc::new(StaticCredentials::new(api_key))
+            }
+        };
+
+        let mut headers = self.default_headers;
+
+        let request_source = match self.request_source {
+            Some(source) => header::HeaderValue::from_str(&source).map_err(|source| {
+                CohereConfigError::InvalidHeader {
+                    name: "Request-Source".to_string(),
+                    source,
+                }
+            })?,
+            None => header::HeaderValue::from_static(COHERE_REQUEST_SOURCE),
+        };
+        headers.insert("Request-Source", request_source);
+
+        headers.insert(
+            header::ACCEPT,
+            header::HeaderValue::from_static("application/json"),
+        );
+        headers.insert(
+            header::CONTENT_TYPE,
+            header::HeaderValue::from_static("application/json"),
+        );
+
+        let transport: Arc<dyn Transport> = match (self.transport, self.http_client) {
+            (Some(transport), _) => transport,
+            (None, Some(client)) => Arc::new(ReqwestTransport::new(client)),
+            (None, None) => {
+                let mut builder = ClientBuilder::new()
+                    .use_rustls_tls()
+                    .timeout(self.timeout.unwrap_or(COHERE_API_TIMEOUT));
+                if let Some(timeout) = self.connect_timeout {
+                    builder = builder.connect_timeout(timeout);
+                }
+                if let Some(proxy) = self.proxy {
+                    builder = builder.proxy(proxy);
+                }
+                if let Some(max) = self.pool_max_idle_per_host {
+                    builder = builder.pool_max_idle_per_host(max);
+                }
+                if let Some(timeout) = self.pool_idle_timeout {
+                    builder = builder.pool_idle_timeout(timeout);
+                }
+                Arc::new(ReqwestTransport::new(
+                    builder.build().map_err(CohereConfigError::HttpClient)?,
+                ))
+            }
+        };
+
+        let transport: Arc<dyn Transport> = match self.cassette {
+            Some(CassetteMode::Record(path)) => Arc::new(RecordingTransport::new(transport, path)),
+            Some(CassetteMode::Replay(path)) => Arc::new(ReplayTransport::from_file(path)?),
+            None => transport,
+        };
+
+        Ok(Cohere {
+            endpoints: Arc::new(endpoints),
+            transport,
+            headers,
+            credentials,
+            retry_policy: self.retry_policy.unwrap_or_else(RetryPolicy::none),
+            rate_limiter: Arc::new(RateLimiter::new(self.rate_limits)),
+            warning_handler: self.warning_handler,
+            middlewares: Arc::new(self.middlewares),
+            metrics: self.metrics,
+            cache: self
+                .cache
+                .map(|config| Arc::new(ResponseCache::new(config))),
+            embedding_store: self.embedding_store,
+            options: RequestOptions::default(),
+        })
+    }
+}
+
+/// Parses the base URL of the API so that routes can be appended to its path.
+fn parse_api_url(api_url: &str) -> Result<Url, CohereConfigError> {
+    let invalid = |reason: String| CohereConfigError::InvalidUrl {
+        url: api_url.to_string(),
+        reason,
+    };
+
+    let url = Url::parse(api_url).map_err(|error| invalid(error.to_string()))?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(invalid(format!("unsupported scheme `{}`", url.scheme())));
+    }
+    if url.cannot_be_a_base() {
+        return Err(invalid("url cannot be used as a base".to_string()));
+    }
+
+    Ok(url)
+}
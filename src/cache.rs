@@ -0,0 +1,170 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use bytes::Bytes;
+use reqwest::header;
+use serde::Serialize;
+
+use crate::CohereApiError;
+
+/// Routes whose responses only depend on the request, and can be served from the cache.
+/// `embed` is cached per text, see [`Cohere::embed`](crate::Cohere::embed).
+pub(crate) const CACHED_ROUTES: [&str; 3] = ["tokenize", "detokenize", "detect-language"];
+
+/// Limits of the in-memory response cache, see [`CohereBuilder::cache`](crate::CohereBuilder::cache).
+#[derive(Clone, Debug)]
+pub struct CacheConfig {
+    /// Maximum number of cached responses and embeddings. The least recently used ones are
+    /// evicted first.
+    pub max_entries: usize,
+    /// How long an entry is served after being cached. `None` keeps entries until evicted.
+    pub ttl: Option<Duration>,
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        Self {
+            max_entries: 10_000,
+            ttl: None,
+        }
+    }
+}
+
+/// How well the cache has been doing so far, see [`Cohere::cache_stats`](crate::Cohere::cache_stats).
+/// Embeddings are counted per text.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    /// Entries removed to make room for new ones.
+    pub evictions: u64,
+    /// Number of entries currently cached.
+    pub entries: usize,
+}
+
+#[derive(Clone)]
+pub(crate) enum Cached {
+    Response {
+        body: Bytes,
+        headers: header::HeaderMap,
+    },
+    Embedding(Vec<f64>),
+}
+
+struct Entry {
+    value: Cached,
+    inserted_at: Instant,
+    /// Position of the entry in the recency order.
+    used_at: u64,
+}
+
+#[derive(Default)]
+struct State {
+    entries: HashMap<String, Entry>,
+    /// Keys of the entries, from the least to the most recently used.
+    recency: BTreeMap<u64, String>,
+    clock: u64,
+    stats: CacheStats,
+}
+
+impl State {
+    fn touch(&mut self, key: &str) {
+        self.clock += 1;
+        let Some(entry) = self.entries.get_mut(key) else {
+            return;
+        };
+        self.recency.remove(&entry.used_at);
+        entry.used_at = self.clock;
+        self.recency.insert(self.clock, key.to_string());
+    }
+
+    fn remove(&mut self, key: &str) {
+        if let Some(entry) = self.entries.remove(key) {
+            self.recency.remove(&entry.used_at);
+        }
+    }
+}
+
+/// A least recently used cache of responses, keyed by route and normalized request body.
+pub(crate) struct ResponseCache {
+    config: CacheConfig,
+    state: Mutex<State>,
+}
+
+impl ResponseCache {
+    pub(crate) fn new(config: CacheConfig) -> Self {
+        Self {
+            config,
+            state: Mutex::default(),
+        }
+    }
+
+    /// The key of a request: its route and body, whose object keys are sorted by serde_json.
+    pub(crate) fn key<Request: Serialize>(
+        route: &str,
+        payload: &Request,
+    ) -> Result<String, CohereApiError> {
+        let body = serde_json::to_value(payload).map_err(CohereApiError::Serialization)?;
+        Ok(format!("{route} {body}"))
+    }
+
+    pub(crate) fn get(&self, key: &str) -> Option<Cached> {
+        let mut state = self.state.lock().expect("cache lock poisoned");
+        let expired = match state.entries.get(key) {
+            None => {
+                state.stats.misses += 1;
+                return None;
+            }
+            Some(entry) => self
+                .config
+                .ttl
+                .is_some_and(|ttl| entry.inserted_at.elapsed() > ttl),
+        };
+        if expired {
+            state.remove(key);
+            state.stats.misses += 1;
+            return None;
+        }
+
+        state.stats.hits += 1;
+        state.touch(key);
+        state.entries.get(key).map(|entry| entry.value.clone())
+    }
+
+    pub(crate) fn insert(&self, key: String, value: Cached) {
+        if self.config.max_entries == 0 {
+            return;
+        }
+
+        let mut state = self.state.lock().expect("cache lock poisoned");
+        state.remove(&key);
+        while state.entries.len() >= self.config.max_entries {
+            let Some((_, oldest)) = state.recency.pop_first() else {
+                break;
+            };
+            state.entries.remove(&oldest);
+            state.stats.evictions += 1;
+        }
+
+        state.entries.insert(
+            key.clone(),
+            Entry {
+                value,
+                inserted_at: Instant::now(),
+                used_at: 0,
+            },
+        );
+        state.touch(&key);
+    }
+
+    pub(crate) fn stats(&self) -> CacheStats {
+        let state = self.state.lock().expect("cache lock poisoned");
+        CacheStats {
+            entries: state.entries.len(),
+            ..state.stats
+        }
+    }
+}
@@ -0,0 +1,308 @@
+use std::{
+    collections::VecDeque,
+    fs,
+    path::{Path, PathBuf},
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+};
+
+use bytes::Bytes;
+use futures_util::{future::BoxFuture, Stream, StreamExt};
+use reqwest::{
+    header::{self, HeaderName, HeaderValue},
+    StatusCode,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    CohereApiError, CohereConfigError, HttpRequest, HttpResponse, ResponseBody, Transport,
+};
+
+const REDACTED: &str = "[REDACTED]";
+
+/// Request/response pairs saved by a [`RecordingTransport`] and served by a [`ReplayTransport`].
+#[derive(Serialize, Deserialize, Debug, Default)]
+struct Cassette {
+    interactions: Vec<Interaction>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct Interaction {
+    request: RecordedRequest,
+    response: RecordedResponse,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct RecordedRequest {
+    method: String,
+    path: String,
+    headers: Vec<(String, String)>,
+    body: Value,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct RecordedResponse {
+    status: u16,
+    headers: Vec<(String, String)>,
+    /// The body, split in the chunks it was received in.
+    chunks: Vec<String>,
+}
+
+impl RecordedRequest {
+    fn new(request: &HttpRequest) -> Self {
+        Self {
+            method: request.method.to_string(),
+            path: request.url.path().to_string(),
+            headers: recorded_headers(&request.headers),
+            body: parse_body(&request.body),
+        }
+    }
+
+    fn matches(&self, request: &HttpRequest) -> bool {
+        self.method == request.method.as_str()
+            && self.path == request.url.path()
+            && self.body == parse_body(&request.body)
+    }
+}
+
+/// Saves the headers, hiding the API key and any other sensitive value.
+fn recorded_headers(headers: &header::HeaderMap) -> Vec<(String, String)> {
+    headers
+        .iter()
+        .map(|(name, value)| {
+            let value = if value.is_sensitive() || name == header::AUTHORIZATION {
+                REDACTED.to_string()
+            } else {
+                String::from_utf8_lossy(value.as_bytes()).into_owned()
+            };
+            (name.to_string(), value)
+        })
+        .collect()
+}
+
+fn parse_body(body: &[u8]) -> Value {
+    serde_json::from_slice(body)
+        .unwrap_or_else(|_| Value::String(String::from_utf8_lossy(body).into_owned()))
+}
+
+fn cassette_error(path: &Path, reason: String) -> CohereConfigError {
+    CohereConfigError::Cassette {
+        path: path.to_path_buf(),
+        reason,
+    }
+}
+
+/// A [`Transport`] forwarding requests to another transport and saving every interaction,
+/// including the chunks of streamed responses, to a cassette file.
+/// The API key and other sensitive headers are redacted.
+pub struct RecordingTransport {
+    inner: Arc<dyn Transport>,
+    recorder: Arc<Recorder>,
+}
+
+struct Recorder {
+    path: PathBuf,
+    cassette: Mutex<Cassette>,
+}
+
+impl Recorder {
+    fn push_chunk(&self, index: usize, chunk: String) {
+        let mut cassette = self.cassette.lock().expect("cassette lock poisoned");
+        cassette.interactions[index].response.chunks.push(chunk);
+    }
+
+    fn save(&self) -> Result<(), CohereConfigError> {
+        let cassette = self.cassette.lock().expect("cassette lock poisoned");
+        let json = serde_json::to_vec_pretty(&*cassette)
+            .map_err(|error| cassette_error(&self.path, error.to_string()))?;
+        fs::write(&self.path, json).map_err(|error| cassette_error(&self.path, error.to_string()))
+    }
+}
+
+impl RecordingTransport {
+    /// Records the interactions of `inner` to the file at `path`, replacing its content.
+    pub fn new<T: Transport + 'static, P: Into<PathBuf>>(inner: T, path: P) -> Self {
+        Self {
+            inner: Arc::new(inner),
+            recorder: Arc::new(Recorder {
+                path: path.into(),
+                cassette: Mutex::new(Cassette::default()),
+            }),
+        }
+    }
+}
+
+impl Transport for RecordingTransport {
+    fn send(&self, request: HttpRequest) -> BoxFuture<'_, Result<HttpResponse, CohereApiError>> {
+        Box::pin(async move {
+            let recorded_request = RecordedRequest::new(&request);
+            let response = self.inner.send(request).await?;
+
+            let index = {
+                let mut cassette = self
+                    .recorder
+                    .cassette
+                    .lock()
+                    .expect("cassette lock poisoned");
+                cassette.interactions.push(Interaction {
+                    request: recorded_request,
+                    response: RecordedResponse {
+                        status: response.status.as_u16(),
+                        headers: recorded_headers(&response.headers),
+                        chunks: Vec::new(),
+                    },
+                });
+                cassette.interactions.len() - 1
+            };
+
+            let body = RecordingBody {
+                body: response.body,
+                recorder: self.recorder.clone(),
+                index,
+                pending: Vec::new(),
+                finished: false,
+            };
+
+            Ok(HttpResponse {
+                status: response.status,
+                headers: response.headers,
+                body: body.boxed(),
+            })
+        })
+    }
+}
+
+/// Response body saving each chunk as it is read, and the cassette once it has been read entirely.
+struct RecordingBody {
+    body: ResponseBody,
+    recorder: Arc<Recorder>,
+    index: usize,
+    /// Bytes of a UTF-8 character split across two chunks.
+    pending: Vec<u8>,
+    finished: bool,
+}
+
+impl RecordingBody {
+    fn record(&mut self, chunk: &[u8]) {
+        self.pending.extend_from_slice(chunk);
+        let valid = match std::str::from_utf8(&self.pending) {
+            Ok(_) => self.pending.len(),
+            Err(error) if error.error_len().is_none() => error.valid_up_to(),
+            Err(_) => self.pending.len(),
+        };
+        if valid == 0 {
+            return;
+        }
+        let rest = self.pending.split_off(valid);
+        let text =
+            String::from_utf8_lossy(&std::mem::replace(&mut self.pending, rest)).into_owned();
+        self.recorder.push_chunk(self.index, text);
+    }
+
+    fn finish(&mut self) -> Result<(), CohereConfigError> {
+        self.finished = true;
+        if !self.pending.is_empty() {
+            let text = String::from_utf8_lossy(&std::mem::take(&mut self.pending)).into_owned();
+            self.recorder.push_chunk(self.index, text);
+        }
+        self.recorder.save()
+    }
+}
+
+impl Stream for RecordingBody {
+    type Item = Result<Bytes, CohereApiError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        if this.finished {
+            return Poll::Ready(None);
+        }
+
+        match this.body.poll_next_unpin(cx) {
+            Poll::Ready(Some(Ok(chunk))) => {
+                this.record(&chunk);
+                Poll::Ready(Some(Ok(chunk)))
+            }
+            Poll::Ready(None) => match this.finish() {
+                Ok(()) => Poll::Ready(None),
+                Err(error) => Poll::Ready(Some(Err(CohereApiError::Transport(Box::new(error))))),
+            },
+            poll => poll,
+        }
+    }
+}
+
+impl Drop for RecordingBody {
+    fn drop(&mut self) {
+        // keep whatever was read of a body dropped before its end
+        if !self.finished {
+            let _ = self.finish();
+        }
+    }
+}
+
+/// A [`Transport`] answering requests from a cassette file saved by a [`RecordingTransport`],
+/// without any network access.
+///
+/// Requests are matched on their method, path and JSON body. Each recorded interaction is served
+/// once, in recording order, and requests without a matching interaction fail.
+pub struct ReplayTransport {
+    interactions: Mutex<VecDeque<Interaction>>,
+}
+
+impl ReplayTransport {
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, CohereConfigError> {
+        let path = path.as_ref();
+        let content = fs::read(path).map_err(|error| cassette_error(path, error.to_string()))?;
+        let cassette = serde_json::from_slice::<Cassette>(&content)
+            .map_err(|error| cassette_error(path, error.to_string()))?;
+
+        Ok(Self {
+            interactions: Mutex::new(cassette.interactions.into()),
+        })
+    }
+}
+
+impl Transport for ReplayTransport {
+    fn send(&self, request: HttpRequest) -> BoxFuture<'_, Result<HttpResponse, CohereApiError>> {
+        let interaction = {
+            let mut interactions = self.interactions.lock().expect("cassette lock poisoned");
+            interactions
+                .iter()
+                .position(|interaction| interaction.request.matches(&request))
+                .and_then(|index| interactions.remove(index))
+        };
+
+        Box::pin(async move {
+            let Some(interaction) = interaction else {
+                return Err(CohereApiError::Transport(
+                    format!(
+                        "no recorded interaction matches {} {}",
+                        request.method,
+                        request.url.path()
+                    )
+                    .into(),
+                ));
+            };
+
+            let response = interaction.response;
+            let status = StatusCode::from_u16(response.status)
+                .map_err(|error| CohereApiError::Transport(Box::new(error)))?;
+            let headers = response
+                .headers
+                .iter()
+                .filter_map(|(name, value)| {
+                    Some((
+                        HeaderName::from_bytes(name.as_bytes()).ok()?,
+                        HeaderValue::from_str(value).ok()?,
+                    ))
+                })
+                .collect();
+            let chunks = response.chunks.into_iter().map(Bytes::from).collect();
+
+            Ok(HttpResponse::from_chunks(status, headers, chunks))
+        })
+    }
+}
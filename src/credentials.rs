@@ -0,0 +1,176 @@
+use std::{
+    fs,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+    time::SystemTime,
+};
+
+use reqwest::header;
+
+use crate::CohereApiError;
+
+/// Supplies the API key of each request, see [`CohereBuilder::credentials`](crate::CohereBuilder::credentials).
+///
+/// The key is queried before every attempt, so providers can rotate keys without rebuilding the client.
+pub trait CredentialProvider: Send + Sync {
+    /// The API key to use for the next request.
+    fn api_key(&self) -> Result<String, CohereApiError>;
+
+    /// Called when the API rejected `rejected` as unauthorized.
+    /// Returning `true` retries the request once with a fresh key. A rejection that is not
+    /// retried fails with [`CohereApiError::InvalidApiKey`]. The default never retries.
+    fn refresh(&self, rejected: &str) -> bool {
+        let _ = rejected;
+        false
+    }
+}
+
+impl<T: CredentialProvider + ?Sized> CredentialProvider for std::sync::Arc<T> {
+    fn api_key(&self) -> Result<String, CohereApiError> {
+        (**self).api_key()
+    }
+
+    fn refresh(&self, rejected: &str) -> bool {
+        (**self).refresh(rejected)
+    }
+}
+
+/// The `Authorization` header of an API key.
+pub(crate) fn bearer(api_key: &str) -> Result<header::HeaderValue, header::InvalidHeaderValue> {
+    let mut authorization = header::HeaderValue::from_str(&format!("Bearer {api_key}"))?;
+    authorization.set_sensitive(true);
+    Ok(authorization)
+}
+
+fn credentials_error<E: Into<Box<dyn std::error::Error + Send + Sync>>>(
+    error: E,
+) -> CohereApiError {
+    CohereApiError::Credentials(error.into())
+}
+
+/// The same API key for every request. This is what [`CohereBuilder::api_key`](crate::CohereBuilder::api_key) uses.
+#[derive(Clone, Debug)]
+pub struct StaticCredentials {
+    api_key: String,
+}
+
+impl StaticCredentials {
+    pub fn new<K: Into<String>>(api_key: K) -> Self {
+        Self {
+            api_key: api_key.into(),
+        }
+    }
+}
+
+impl CredentialProvider for StaticCredentials {
+    fn api_key(&self) -> Result<String, CohereApiError> {
+        Ok(self.api_key.clone())
+    }
+}
+
+/// The API key read from an env variable on every request.
+#[derive(Clone, Debug)]
+pub struct EnvCredentials {
+    var: String,
+}
+
+impl EnvCredentials {
+    pub fn new<V: Into<String>>(var: V) -> Self {
+        Self { var: var.into() }
+    }
+}
+
+impl CredentialProvider for EnvCredentials {
+    fn api_key(&self) -> Result<String, CohereApiError> {
+        std::env::var(&self.var)
+            .map_err(|error| credentials_error(format!("env variable `{}`: {error}", self.var)))
+    }
+
+    fn refresh(&self, rejected: &str) -> bool {
+        self.api_key().is_ok_and(|api_key| api_key != rejected)
+    }
+}
+
+/// The API key read from a file, e.g. a mounted secret, and read again whenever the file changes.
+/// Surrounding whitespace is ignored.
+#[derive(Debug)]
+pub struct FileCredentials {
+    path: PathBuf,
+    /// The key read from the file, with the modification time of the file at that point.
+    cached: Mutex<Option<(SystemTime, String)>>,
+}
+
+impl FileCredentials {
+    pub fn new<P: Into<PathBuf>>(path: P) -> Self {
+        Self {
+            path: path.into(),
+            cached: Mutex::new(None),
+        }
+    }
+}
+
+impl CredentialProvider for FileCredentials {
+    fn api_key(&self) -> Result<String, CohereApiError> {
+        let file_error = |error: std::io::Error| {
+            credentials_error(format!("file `{}`: {error}", self.path.display()))
+        };
+
+        let modified = fs::metadata(&self.path)
+            .and_then(|metadata| metadata.modified())
+            .map_err(file_error)?;
+
+        let mut cached = self.cached.lock().expect("credentials lock poisoned");
+        if let Some((read_at, api_key)) = &*cached {
+            if *read_at == modified {
+                return Ok(api_key.clone());
+            }
+        }
+
+        let api_key = fs::read_to_string(&self.path)
+            .map_err(file_error)?
+            .trim()
+            .to_string();
+        *cached = Some((modified, api_key.clone()));
+        Ok(api_key)
+    }
+
+    fn refresh(&self, rejected: &str) -> bool {
+        // the file may have been replaced within the resolution of its modification time
+        *self.cached.lock().expect("credentials lock poisoned") = None;
+        self.api_key().is_ok_and(|api_key| api_key != rejected)
+    }
+}
+
+/// Several API keys used in turn, one per request, e.g. to spread the load over the rate limits
+/// of several keys. A rejected request is retried with the next key.
+#[derive(Debug)]
+pub struct RoundRobinCredentials {
+    api_keys: Vec<String>,
+    next: AtomicUsize,
+}
+
+impl RoundRobinCredentials {
+    pub fn new<I: IntoIterator<Item = K>, K: Into<String>>(api_keys: I) -> Self {
+        Self {
+            api_keys: api_keys.into_iter().map(Into::into).collect(),
+            next: AtomicUsize::new(0),
+        }
+    }
+}
+
+impl CredentialProvider for RoundRobinCredentials {
+    fn api_key(&self) -> Result<String, CohereApiError> {
+        if self.api_keys.is_empty() {
+            return Err(credentials_error("no API key to rotate over"));
+        }
+        let index = self.next.fetch_add(1, Ordering::Relaxed) % self.api_keys.len();
+        Ok(self.api_keys[index].clone())
+    }
+
+    fn refresh(&self, rejected: &str) -> bool {
+        self.api_keys.iter().any(|api_key| api_key != rejected)
+    }
+}
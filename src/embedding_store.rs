@@ -0,0 +1,161 @@
+use std::{
+    collections::HashMap,
+    fs::{self, File, OpenOptions},
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    api::embed::{EmbedInputType, EmbedRequest},
+    CohereConfigError,
+};
+
+/// Embeddings persisted to a local file, so that they survive between runs of the process,
+/// see [`CohereBuilder::embedding_store`](crate::CohereBuilder::embedding_store).
+///
+/// Embeddings are keyed by model, input type, truncation and a hash of the text, so that only
+/// new or changed texts are sent to the API. Requests without an explicit model bypass the
+/// store, as the default model of the API may change. The file is append-only, one JSON entry
+/// per line.
+pub struct EmbeddingStore {
+    path: PathBuf,
+    state: Mutex<State>,
+}
+
+struct State {
+    embeddings: HashMap<String, Vec<f64>>,
+    file: File,
+}
+
+#[derive(Serialize, Deserialize)]
+struct StoredEmbedding {
+    key: String,
+    embedding: Vec<f64>,
+}
+
+impl EmbeddingStore {
+    /// Opens the store at `path`, creating the file if it does not exist.
+    pub fn open<P: Into<PathBuf>>(path: P) -> Result<Self, CohereConfigError> {
+        let path = path.into();
+        let store_error = |error: std::io::Error| CohereConfigError::EmbeddingStore {
+            path: path.clone(),
+            reason: error.to_string(),
+        };
+
+        let mut embeddings = HashMap::new();
+        // whether the last line was cut short, e.g. by a crash while writing
+        let mut partial = false;
+        if path.exists() {
+            let contents = fs::read(&path).map_err(store_error)?;
+            partial = !contents.is_empty() && !contents.ends_with(b"\n");
+            for line in contents.split(|byte| *byte == b'\n') {
+                // an invalid line, such as a partial one, is skipped
+                if let Ok(stored) = serde_json::from_slice::<StoredEmbedding>(line) {
+                    embeddings.insert(stored.key, stored.embedding);
+                }
+            }
+        } else if let Some(parent) = path
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+        {
+            fs::create_dir_all(parent).map_err(store_error)?;
+        }
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .map_err(store_error)?;
+        // ends the partial line, so that the next entry is not appended to it
+        if partial {
+            file.write_all(b"\n").map_err(store_error)?;
+        }
+
+        Ok(Self {
+            path,
+            state: Mutex::new(State { embeddings, file }),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Number of stored embeddings.
+    pub fn len(&self) -> usize {
+        self.state
+            .lock()
+            .expect("embedding store lock poisoned")
+            .embeddings
+            .len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The key of a text embedded with the model, input type and truncation of the request, or
+    /// `None` when the request has no explicit model: the default model of the API may change,
+    /// so its embeddings are not stored.
+    pub(crate) fn key(request: &EmbedRequest<'_>, text: &str) -> Option<String> {
+        let model = serde_json::to_string(request.model.as_ref()?).ok()?;
+        let input_type = request
+            .input_type
+            .as_ref()
+            .map(EmbedInputType::to_string)
+            .unwrap_or_default();
+        Some(format!(
+            "{model}:{input_type}:{}:{:032x}",
+            request.truncate,
+            fnv1a_128(text.as_bytes())
+        ))
+    }
+
+    pub(crate) fn get(&self, key: &str) -> Option<Vec<f64>> {
+        let state = self.state.lock().expect("embedding store lock poisoned");
+        state.embeddings.get(key).cloned()
+    }
+
+    /// Stores the embeddings and writes them to the file. They are kept in memory even when
+    /// writing fails.
+    pub(crate) fn insert(&self, embeddings: Vec<(String, Vec<f64>)>) -> std::io::Result<()> {
+        let mut state = self.state.lock().expect("embedding store lock poisoned");
+
+        let mut writer = BufWriter::new(&state.file);
+        let written = embeddings
+            .iter()
+            .try_for_each(|(key, embedding)| {
+                let line = serde_json::to_string(&StoredEmbedding {
+                    key: key.clone(),
+                    embedding: embedding.clone(),
+                })?;
+                writeln!(writer, "{line}")
+            })
+            .and_then(|()| writer.flush());
+        drop(writer);
+
+        state.embeddings.extend(embeddings);
+        written
+    }
+}
+
+impl std::fmt::Debug for EmbeddingStore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EmbeddingStore")
+            .field("path", &self.path)
+            .finish_non_exhaustive()
+    }
+}
+
+/// 128 bit FNV-1a hash, stable across runs and platforms unlike the hashers of the standard library.
+fn fnv1a_128(bytes: &[u8]) -> u128 {
+    const OFFSET_BASIS: u128 = 0x6c62272e07bb014262b821756295c58d;
+    const PRIME: u128 = 0x0000000001000000000000000000013b;
+
+    bytes.iter().fold(OFFSET_BASIS, |hash, byte| {
+        (hash ^ u128::from(*byte)).wrapping_mul(PRIME)
+    })
+}
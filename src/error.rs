@@ -0,0 +1,241 @@
+use std::time::Duration;
+
+use reqwest::{header, StatusCode};
+use serde::Deserialize;
+use thiserror::Error;
+
+use crate::FieldError;
+
+/// Headers that may carry the identifier of a request, in order of preference.
+const REQUEST_ID_HEADERS: [&str; 2] = ["x-request-id", "x-debug-trace-id"];
+
+#[derive(Error, Debug)]
+pub enum CohereApiError {
+    #[error("Unexpected request error")]
+    RequestError(reqwest::Error),
+    #[error("Bad request: `{}`", .0.message)]
+    BadRequest(ApiErrorDetails),
+    /// A `401` response to a single attempt, as seen by [`Middleware::on_error`](crate::Middleware::on_error).
+    /// Calls rejected as unauthorized fail with [`CohereApiError::InvalidApiKey`].
+    #[error("Unauthorized: `{}`", .0.message)]
+    Unauthorized(ApiErrorDetails),
+    #[error("Forbidden: `{}`", .0.message)]
+    Forbidden(ApiErrorDetails),
+    #[error("Not found: `{}`", .0.message)]
+    NotFound(ApiErrorDetails),
+    #[error("Rate limited: `{}`", .details.message)]
+    RateLimited {
+        /// How long the API asked to wait before trying again.
+        retry_after: Option<Duration>,
+        details: ApiErrorDetails,
+    },
+    #[error("Service unavailable: `{}`", .0.message)]
+    ServiceUnavailable(ApiErrorDetails),
+    #[error("Request timed out")]
+    Timeout,
+    #[error("Failed to deserialize API response")]
+    Deserialization {
+        /// The raw response body.
+        body: String,
+        request_id: Option<String>,
+        #[source]
+        source: serde_json::Error,
+    },
+    #[error("Request was cancelled")]
+    Cancelled,
+    #[error("Invalid request option: {0}")]
+    InvalidOption(String),
+    #[error("Invalid request: {}", crate::validation::describe(.0))]
+    Validation(Vec<FieldError>),
+    #[error("Failed to serialize request")]
+    Serialization(#[source] serde_json::Error),
+    #[error("Transport error")]
+    Transport(#[source] Box<dyn std::error::Error + Send + Sync>),
+    #[error("API request failed with status code `{}` and error message `{}`", .0.status, .0.message)]
+    ApiError(ApiErrorDetails),
+    /// The API key was rejected, after one refresh when the credential provider supports it.
+    /// Holds the details of the last `401` response, or `None` when reported by
+    /// [`Cohere::check_api_key`](crate::Cohere::check_api_key).
+    #[error("API key is invalid")]
+    InvalidApiKey(Option<ApiErrorDetails>),
+    #[error("Failed to get the API key")]
+    Credentials(#[source] Box<dyn std::error::Error + Send + Sync>),
+    #[error("Unknown error")]
+    Unknown,
+}
+
+#[derive(Error, Debug)]
+pub enum CohereStreamError {
+    #[error("Unexpected deserialization error")]
+    RequestError(#[from] serde_json::error::Error),
+    #[error("Unknown error `{0}`")]
+    Unknown(String),
+    #[error("Failed to read the stream")]
+    Transport(#[source] CohereApiError),
+    #[error("The stream ended before its `stream-end` event")]
+    Incomplete,
+}
+
+/// Invalid client configuration, returned when constructing a [`Cohere`](crate::Cohere) client.
+#[derive(Error, Debug)]
+pub enum CohereConfigError {
+    #[error("please provide a Cohere API key with the 'CO_API_KEY' env variable")]
+    MissingApiKey,
+    #[error("API key contains characters that are not allowed in a header")]
+    InvalidApiKey(#[source] header::InvalidHeaderValue),
+    #[error("invalid value for header `{name}`")]
+    InvalidHeader {
+        name: String,
+        #[source]
+        source: header::InvalidHeaderValue,
+    },
+    #[error("invalid API url `{url}`: {reason}")]
+    InvalidUrl { url: String, reason: String },
+    #[error("invalid rate limit for route `{route}`: {reason}")]
+    InvalidRateLimit { route: String, reason: String },
+    #[error("failed to initialize HTTP client")]
+    HttpClient(#[source] reqwest::Error),
+    #[error("failed to use cassette `{}`: {reason}", .path.display())]
+    Cassette {
+        path: std::path::PathBuf,
+        reason: String,
+    },
+    #[error("failed to open embedding store `{}`: {reason}", .path.display())]
+    EmbeddingStore {
+        path: std::path::PathBuf,
+        reason: String,
+    },
+    #[error("failed to start the runtime of the blocking client")]
+    Runtime(#[source] std::io::Error),
+}
+
+/// Everything the API returned alongside an unsuccessful status code.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ApiErrorDetails {
+    pub status: StatusCode,
+    /// The error message returned by the API.
+    pub message: String,
+    /// The raw response body.
+    pub body: String,
+    /// Identifier of the failed request, useful when contacting Cohere support.
+    pub request_id: Option<String>,
+    /// How long the API asked to wait before retrying, from the `Retry-After` header.
+    pub retry_after: Option<Duration>,
+}
+
+#[derive(Deserialize, Debug)]
+struct CohereApiErrorResponse {
+    message: String,
+}
+
+impl CohereApiError {
+    /// Whether repeating the same request may succeed.
+    /// Timeouts are not considered retryable since the request may already have been processed.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            CohereApiError::RequestError(error) => error.is_connect(),
+            CohereApiError::RateLimited { .. } | CohereApiError::ServiceUnavailable(_) => true,
+            CohereApiError::ApiError(details) => matches!(
+                details.status,
+                StatusCode::BAD_GATEWAY | StatusCode::GATEWAY_TIMEOUT
+            ),
+            _ => false,
+        }
+    }
+
+    /// How long the API asked to wait before retrying, if it did.
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            CohereApiError::RateLimited { retry_after, .. } => *retry_after,
+            error => error.details().and_then(|details| details.retry_after),
+        }
+    }
+
+    /// A short name of the variant, e.g. `"rate_limited"`, usable as a metric label.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            CohereApiError::RequestError(_) => "request_error",
+            CohereApiError::BadRequest(_) => "bad_request",
+            CohereApiError::Unauthorized(_) => "unauthorized",
+            CohereApiError::Forbidden(_) => "forbidden",
+            CohereApiError::NotFound(_) => "not_found",
+            CohereApiError::RateLimited { .. } => "rate_limited",
+            CohereApiError::ServiceUnavailable(_) => "service_unavailable",
+            CohereApiError::Timeout => "timeout",
+            CohereApiError::Deserialization { .. } => "deserialization",
+            CohereApiError::Cancelled => "cancelled",
+            CohereApiError::InvalidOption(_) => "invalid_option",
+            CohereApiError::Validation(_) => "validation",
+            CohereApiError::Serialization(_) => "serialization",
+            CohereApiError::Transport(_) => "transport",
+            CohereApiError::ApiError(_) => "api_error",
+            CohereApiError::InvalidApiKey(_) => "invalid_api_key",
+            CohereApiError::Credentials(_) => "credentials",
+            CohereApiError::Unknown => "unknown",
+        }
+    }
+
+    /// The response details for errors returned by the API.
+    pub fn details(&self) -> Option<&ApiErrorDetails> {
+        match self {
+            CohereApiError::BadRequest(details)
+            | CohereApiError::Unauthorized(details)
+            | CohereApiError::Forbidden(details)
+            | CohereApiError::NotFound(details)
+            | CohereApiError::RateLimited { details, .. }
+            | CohereApiError::ServiceUnavailable(details)
+            | CohereApiError::ApiError(details) => Some(details),
+            CohereApiError::InvalidApiKey(details) => details.as_ref(),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn from_response(
+        status: StatusCode,
+        headers: &header::HeaderMap,
+        body: String,
+    ) -> Self {
+        let message = serde_json::from_str::<CohereApiErrorResponse>(&body)
+            .map(|error| error.message)
+            .unwrap_or_else(|_| format!("Unknown API Error: {}", body));
+        let details = ApiErrorDetails {
+            status,
+            message,
+            body,
+            request_id: request_id(headers),
+            retry_after: crate::retry::retry_after(headers),
+        };
+
+        match status {
+            StatusCode::BAD_REQUEST => CohereApiError::BadRequest(details),
+            StatusCode::UNAUTHORIZED => CohereApiError::Unauthorized(details),
+            StatusCode::FORBIDDEN => CohereApiError::Forbidden(details),
+            StatusCode::NOT_FOUND => CohereApiError::NotFound(details),
+            StatusCode::TOO_MANY_REQUESTS => CohereApiError::RateLimited {
+                retry_after: details.retry_after,
+                details,
+            },
+            StatusCode::SERVICE_UNAVAILABLE => CohereApiError::ServiceUnavailable(details),
+            _ => CohereApiError::ApiError(details),
+        }
+    }
+}
+
+impl From<reqwest::Error> for CohereApiError {
+    fn from(error: reqwest::Error) -> Self {
+        // a connect timeout means the endpoint is unreachable, and is kept as a connect error so
+        // that it is retried and failed over
+        if error.is_timeout() && !error.is_connect() {
+            CohereApiError::Timeout
+        } else {
+            CohereApiError::RequestError(error)
+        }
+    }
+}
+
+pub(crate) fn request_id(headers: &header::HeaderMap) -> Option<String> {
+    REQUEST_ID_HEADERS
+        .iter()
+        .find_map(|name| headers.get(*name)?.to_str().ok())
+        .map(str::to_string)
+}
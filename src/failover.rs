@@ -0,0 +1,167 @@
+use std::{
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use reqwest::{StatusCode, Url};
+
+use crate::CohereApiError;
+
+/// Controls the circuit breaker of each API endpoint, see [`CohereBuilder::api_urls`](crate::CohereBuilder::api_urls).
+///
+/// An endpoint failing `failure_threshold` times in a row is skipped for `cooldown`. After that,
+/// the next request probes it again: a success puts it back in rotation, a failure skips it for
+/// another `cooldown`.
+#[derive(Clone, Debug)]
+pub struct FailoverPolicy {
+    /// Number of consecutive failures after which an endpoint is skipped.
+    pub failure_threshold: u32,
+    /// How long a failing endpoint is skipped before being probed again.
+    pub cooldown: Duration,
+}
+
+impl Default for FailoverPolicy {
+    fn default() -> Self {
+        Self {
+            failure_threshold: 3,
+            cooldown: Duration::from_secs(30),
+        }
+    }
+}
+
+/// State of an API endpoint, as returned by [`Cohere::endpoints`](crate::Cohere::endpoints).
+#[derive(Clone, Debug, PartialEq)]
+pub struct EndpointHealth {
+    pub url: Url,
+    /// Number of failures since the last success.
+    pub consecutive_failures: u32,
+    /// Whether the endpoint is used for requests, i.e. its circuit is closed or its cooldown is over.
+    pub available: bool,
+}
+
+/// Whether the error is worth trying another endpoint for: the endpoint was unreachable, including
+/// when connecting to it timed out, or failed with a server error, so the request was not handled.
+pub(crate) fn is_failover(error: &CohereApiError) -> bool {
+    match error {
+        CohereApiError::RequestError(error) => error.is_connect(),
+        error => error
+            .details()
+            .is_some_and(|details| details.status.is_server_error()),
+    }
+}
+
+/// Whether a response with the given status shows that the endpoint is healthy.
+pub(crate) fn is_healthy(status: StatusCode) -> bool {
+    !status.is_server_error()
+}
+
+pub(crate) struct Endpoint {
+    url: Url,
+    state: Mutex<EndpointState>,
+}
+
+#[derive(Default)]
+struct EndpointState {
+    consecutive_failures: u32,
+    /// Until when the endpoint is skipped, once its circuit is open.
+    open_until: Option<Instant>,
+}
+
+impl Endpoint {
+    /// The url of the given route on this endpoint.
+    pub(crate) fn route_url(&self, route: &str) -> Url {
+        let mut url = self.url.clone();
+        // the base url is checked to have a path when the client is built
+        if let Ok(mut segments) = url.path_segments_mut() {
+            segments.pop_if_empty().extend(route.split('/'));
+        }
+        url
+    }
+
+    pub(crate) fn record_success(&self) {
+        *self.state.lock().expect("endpoint lock poisoned") = EndpointState::default();
+    }
+
+    pub(crate) fn record_failure(&self, policy: &FailoverPolicy) {
+        let mut state = self.state.lock().expect("endpoint lock poisoned");
+        state.consecutive_failures += 1;
+        // a failed probe opens the circuit again right away
+        if state.consecutive_failures >= policy.failure_threshold || state.open_until.is_some() {
+            state.open_until = Some(Instant::now() + policy.cooldown);
+        }
+    }
+
+    fn open_until(&self, now: Instant) -> Option<Instant> {
+        let state = self.state.lock().expect("endpoint lock poisoned");
+        state.open_until.filter(|open_until| *open_until > now)
+    }
+
+    fn health(&self, now: Instant) -> EndpointHealth {
+        let state = self.state.lock().expect("endpoint lock poisoned");
+        EndpointHealth {
+            url: self.url.clone(),
+            consecutive_failures: state.consecutive_failures,
+            available: state
+                .open_until
+                .filter(|open_until| *open_until > now)
+                .is_none(),
+        }
+    }
+}
+
+/// The API endpoints of a client, in order of preference.
+pub(crate) struct Endpoints {
+    endpoints: Vec<Endpoint>,
+    pub(crate) policy: FailoverPolicy,
+}
+
+impl Endpoints {
+    pub(crate) fn new(urls: Vec<Url>, policy: FailoverPolicy) -> Self {
+        Self {
+            endpoints: urls
+                .into_iter()
+                .map(|url| Endpoint {
+                    url,
+                    state: Mutex::default(),
+                })
+                .collect(),
+            policy,
+        }
+    }
+
+    /// The endpoints to try for a request: the available ones in order of preference or, when
+    /// every circuit is open, the one closest to the end of its cooldown.
+    pub(crate) fn candidates(&self) -> Vec<&Endpoint> {
+        let now = Instant::now();
+        let available: Vec<&Endpoint> = self
+            .endpoints
+            .iter()
+            .filter(|endpoint| endpoint.open_until(now).is_none())
+            .collect();
+        if !available.is_empty() {
+            return available;
+        }
+
+        self.endpoints
+            .iter()
+            .min_by_key(|endpoint| endpoint.open_until(now))
+            .into_iter()
+            .collect()
+    }
+
+    /// The endpoints whose circuit is open.
+    pub(crate) fn unavailable(&self) -> impl Iterator<Item = &Endpoint> {
+        let now = Instant::now();
+        self.endpoints
+            .iter()
+            .filter(move |endpoint| endpoint.open_until(now).is_some())
+    }
+
+    pub(crate) fn health(&self) -> Vec<EndpointHealth> {
+        let now = Instant::now();
+        self.endpoints
+            .iter()
+            .map(|endpoint| endpoint.health(now))
+            .collect()
+    }
+}
@@ -1,4 +1,8 @@
-use std::time::Duration;
+use std::{
+    future::{ready, Future},
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use api::{
     chat::{ChatRequest, ChatResponse, ChatStreamRequest, StreamEvent},
@@ -11,126 +15,562 @@ use api::{
     summarize::{SummarizeRequest, SummarizeResponse},
     tokenize::{TokenizeRequest, TokenizeResponse},
 };
-use reqwest::{header, ClientBuilder, StatusCode, Url};
-use tokio::sync::mpsc::{channel, Receiver};
+use bytes::Bytes;
+use cache::{Cached, ResponseCache, CACHED_ROUTES};
+use failover::Endpoints;
+use futures_timer::Delay;
+use futures_util::{
+    future::{pending, select, Either},
+    StreamExt,
+};
+use meta::ResponseEnvelope;
+use metrics::StreamMetrics;
+use rate_limit::RateLimiter;
+use reqwest::{header, Method};
+use stream::EventDecoder;
 
 const COHERE_API_BASE_URL: &str = "https://api.cohere.ai";
 const COHERE_API_V1: &str = "v1";
 const COHERE_API_TIMEOUT: Duration = Duration::from_secs(120);
 
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
-use thiserror::Error;
 
 pub mod api;
-
-#[derive(Error, Debug)]
-pub enum CohereApiError {
-    #[error("Unexpected request error")]
-    RequestError(#[from] reqwest::Error),
-    #[error("API request failed with status code `{0}` and error message `{1}`")]
-    ApiError(StatusCode, String),
-    #[error("API key is invalid")]
-    InvalidApiKey,
-    #[error("Unknown error")]
-    Unknown,
-}
-
-#[derive(Error, Debug)]
-pub enum CohereStreamError {
-    #[error("Unexpected deserialization error")]
-    RequestError(#[from] serde_json::error::Error),
-    #[error("Unknown error `{0}`")]
-    Unknown(String),
-}
+#[cfg(feature = "blocking")]
+pub mod blocking;
+mod builder;
+mod cache;
+mod cassette;
+mod credentials;
+mod embedding_store;
+mod error;
+mod failover;
+mod meta;
+mod metrics;
+mod middleware;
+mod options;
+mod rate_limit;
+mod retry;
+mod stream;
+mod telemetry;
+mod transport;
+mod validation;
+mod warning;
+
+pub use builder::CohereBuilder;
+pub use cache::{CacheConfig, CacheStats};
+pub use cassette::{RecordingTransport, ReplayTransport};
+pub use credentials::{
+    CredentialProvider, EnvCredentials, FileCredentials, RoundRobinCredentials, StaticCredentials,
+};
+pub use embedding_store::EmbeddingStore;
+pub use error::{ApiErrorDetails, CohereApiError, CohereConfigError, CohereStreamError};
+pub use failover::{EndpointHealth, FailoverPolicy};
+pub use meta::{ApiVersion, BilledUnits, ResponseMeta, WithMeta};
+#[cfg(feature = "prometheus")]
+pub use metrics::PrometheusMetrics;
+pub use metrics::{CallMetrics, InMemoryMetrics, Metrics, RouteMetrics, LATENCY_BUCKETS};
+pub use middleware::{Middleware, MiddlewareRequest, MiddlewareResponse};
+pub use options::{CancellationToken, Cancelled, RequestOptions};
+pub use rate_limit::{RateLimit, RateLimitStats};
+pub use retry::RetryPolicy;
+pub use stream::{ChatStream, ChatStreamAggregator};
+pub use transport::{HttpRequest, HttpResponse, ReqwestTransport, ResponseBody, Transport};
+pub use validation::{FieldError, Validate};
+pub use warning::{ApiWarning, WarningHandler, WarningKind};
 
 /// Cohere Rust SDK to build natural language understanding and generation into your product with a few lines of code.
+///
+/// Cloning the client is cheap, clones share the same connection pool, rate limiter and hooks.
+#[derive(Clone)]
 pub struct Cohere {
-    api_url: String,
-    client: reqwest::Client,
+    endpoints: Arc<Endpoints>,
+    transport: Arc<dyn Transport>,
+    headers: header::HeaderMap,
+    credentials: Arc<dyn CredentialProvider>,
+    retry_policy: RetryPolicy,
+    rate_limiter: Arc<RateLimiter>,
+    warning_handler: Option<WarningHandler>,
+    middlewares: Arc<Vec<Arc<dyn Middleware>>>,
+    metrics: Option<Arc<dyn Metrics>>,
+    cache: Option<Arc<ResponseCache>>,
+    embedding_store: Option<Arc<EmbeddingStore>>,
+    options: RequestOptions,
 }
 
-#[derive(Deserialize, Debug)]
-struct CohereCheckApiKeyResponse {
-    valid: bool,
+/// A successful response along with how it was obtained.
+struct Sent {
+    response: HttpResponse,
+    attempts: u32,
+    rate_limit_wait: Duration,
 }
 
 #[derive(Deserialize, Debug)]
-struct CohereApiErrorResponse {
-    message: String,
+struct CohereCheckApiKeyResponse {
+    valid: bool,
 }
 
 impl Default for Cohere {
+    /// Creates a client using the API key from the `CO_API_KEY` env variable.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the env variable is missing, see [`Cohere::from_env`] for a fallible version.
     fn default() -> Self {
-        let api_key = std::env::var("CO_API_KEY")
-            .expect("please provide a Cohere API key with the 'CO_API_KEY' env variable");
-        Cohere::new(format!("{COHERE_API_BASE_URL}/{COHERE_API_V1}"), api_key)
+        Cohere::builder().build()
     }
 }
 
 impl Cohere {
+    /// Creates a client for the given API url and key.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the url or key are invalid, see [`Cohere::try_new`] for a fallible version.
     pub fn new<U: Into<String>, K: Into<String>>(api_url: U, api_key: K) -> Self {
-        let api_url: String = api_url.into();
-        let api_key: String = api_key.into();
+        Cohere::builder().api_url(api_url).api_key(api_key).build()
+    }
 
-        let mut headers = header::HeaderMap::new();
+    /// Creates a client for the given API url and key, returning an error if they are invalid.
+    pub fn try_new<U: Into<String>, K: Into<String>>(
+        api_url: U,
+        api_key: K,
+    ) -> Result<Self, CohereConfigError> {
+        Cohere::builder()
+            .api_url(api_url)
+            .api_key(api_key)
+            .try_build()
+    }
 
-        let mut authorization = header::HeaderValue::from_str(&format!("Bearer {api_key}"))
-            .expect("failed to construct authorization header!");
-        authorization.set_sensitive(true);
-        headers.insert(header::AUTHORIZATION, authorization);
+    /// Creates a client using the API key from the `CO_API_KEY` env variable.
+    pub fn from_env() -> Result<Self, CohereConfigError> {
+        Cohere::builder().try_build()
+    }
 
-        headers.insert(
-            "Request-Source",
-            header::HeaderValue::from_static("rust-sdk"),
-        );
+    /// Returns a builder to configure timeouts, proxies, headers or a custom HTTP client.
+    pub fn builder() -> CohereBuilder {
+        CohereBuilder::new()
+    }
 
-        headers.insert(
-            header::ACCEPT,
-            header::HeaderValue::from_static("application/json"),
-        );
-        headers.insert(
-            header::CONTENT_TYPE,
-            header::HeaderValue::from_static("application/json"),
-        );
+    /// Returns how much calls to the given route have been delayed by the client-side rate limiter,
+    /// or `None` if the route is not rate limited.
+    pub fn rate_limit_stats(&self, route: &str) -> Option<RateLimitStats> {
+        self.rate_limiter.stats(route)
+    }
 
-        let client = ClientBuilder::new()
-            .default_headers(headers)
-            .use_rustls_tls()
-            .timeout(COHERE_API_TIMEOUT)
-            .build()
-            .expect("failed to initialize HTTP client!");
+    /// Returns the hits and misses of the response cache, or `None` if it is not enabled.
+    pub fn cache_stats(&self) -> Option<CacheStats> {
+        self.cache.as_ref().map(|cache| cache.stats())
+    }
 
-        Cohere { api_url, client }
+    /// Returns the API endpoints of the client, in order of preference, with the state of their
+    /// circuit breaker.
+    pub fn endpoints(&self) -> Vec<EndpointHealth> {
+        self.endpoints.health()
     }
 
-    async fn request<Request: Serialize, Response: DeserializeOwned>(
+    /// Probes the endpoints skipped by the circuit breaker with a `check-api-key` call, putting
+    /// the ones that answer back in rotation, and returns the state of all the endpoints.
+    ///
+    /// Endpoints are also probed by live requests once their cooldown is over. To recover them
+    /// without waiting for traffic, run [`Cohere::probe_loop`] in the background.
+    pub async fn probe_endpoints(&self) -> Vec<EndpointHealth> {
+        for endpoint in self.endpoints.unavailable() {
+            let mut headers = match self.request_headers() {
+                Ok(headers) => headers,
+                Err(_) => break,
+            };
+            if self.authorize(&mut headers).is_err() {
+                break;
+            }
+
+            let request = HttpRequest {
+                method: Method::POST,
+                url: endpoint.route_url("check-api-key"),
+                headers,
+                body: Bytes::from_static(b"null"),
+                timeout: self.options.timeout,
+            };
+            match self.transport.send(request).await {
+                Ok(response) if failover::is_healthy(response.status) => endpoint.record_success(),
+                _ => endpoint.record_failure(&self.endpoints.policy),
+            }
+        }
+        self.endpoints.health()
+    }
+
+    /// Returns a future calling [`Cohere::probe_endpoints`] every `interval`, forever. It only
+    /// sends requests while some endpoint is skipped, and does not depend on any async runtime:
+    /// spawn it as a task of yours, and drop or abort it to stop probing.
+    ///
+    /// ```no_run
+    /// # async fn run(co: cohere_rust::Cohere) {
+    /// let probes = tokio::spawn(co.probe_loop(std::time::Duration::from_secs(10)));
+    /// // ...
+    /// probes.abort();
+    /// # }
+    /// ```
+    pub fn probe_loop(&self, interval: Duration) -> impl Future<Output = ()> + Send + 'static {
+        let client = self.clone();
+        async move {
+            loop {
+                Delay::new(interval).await;
+                client.probe_endpoints().await;
+            }
+        }
+    }
+
+    /// Returns a client that applies the given options to all of its calls.
+    /// It shares the configuration and state of this client.
+    pub fn with_options(&self, options: RequestOptions) -> Cohere {
+        Cohere {
+            options,
+            ..self.clone()
+        }
+    }
+
+    async fn request<Request: Serialize + Validate, Response: DeserializeOwned>(
         &self,
         route: &'static str,
         payload: Request,
-    ) -> Result<Response, CohereApiError> {
-        let url =
-            Url::parse(&format!("{}/{route}", self.api_url)).expect("api url should be valid");
+    ) -> Result<WithMeta<Response>, CohereApiError> {
+        payload.validate().map_err(CohereApiError::Validation)?;
+
+        let cache_key = match &self.cache {
+            Some(cache) if CACHED_ROUTES.contains(&route) => {
+                let key = ResponseCache::key(route, &payload)?;
+                if let Some(Cached::Response { body, headers }) = cache.get(&key) {
+                    let mut response = Self::parse_response::<Response>(&body, headers)?;
+                    response.meta.cached = true;
+                    return Ok(response);
+                }
+                Some(key)
+            }
+            _ => None,
+        };
+
+        let model = self.request_model(&payload);
+        let span = telemetry::request_span(route, model.as_deref());
+        let started = Instant::now();
+        let result = telemetry::instrument(
+            &span,
+            self.cancellable(self.receive(route, payload, cache_key)),
+        )
+        .await;
+
+        self.record_metrics(&CallMetrics {
+            route,
+            model: model.as_deref(),
+            latency: started.elapsed(),
+            billed_units: result
+                .as_ref()
+                .ok()
+                .and_then(|response| response.meta.billed_units.as_ref()),
+            error: result.as_ref().err(),
+        });
+        result
+    }
 
-        let response = self.client.post(url).json(&payload).send().await?;
+    /// The model of a request, used to label its span and metrics.
+    fn request_model<Request: Serialize>(&self, payload: &Request) -> Option<String> {
+        if self.metrics.is_none() && !cfg!(feature = "tracing") {
+            return None;
+        }
+        let payload = serde_json::to_value(payload).ok()?;
+        payload.get("model")?.as_str().map(str::to_string)
+    }
 
-        // Check for any API Warnings
-        if let Some(warning) = response.headers().get("X-API-Warning") {
-            eprintln!("Warning: {:?}", String::from_utf8_lossy(warning.as_bytes()));
+    fn record_metrics(&self, call: &CallMetrics<'_>) {
+        if let Some(metrics) = &self.metrics {
+            metrics.record(call);
         }
+    }
 
-        if response.status().is_client_error() || response.status().is_server_error() {
-            let status = response.status();
-            let text = response.text().await?;
-            Err(CohereApiError::ApiError(
-                status,
-                serde_json::from_str::<CohereApiErrorResponse>(&text)
-                    .unwrap_or(CohereApiErrorResponse {
-                        message: format!("Unknown API Error: {}", text),
+    async fn receive<Request: Serialize, Response: DeserializeOwned>(
+        &self,
+        route: &'static str,
+        payload: Request,
+        cache_key: Option<String>,
+    ) -> Result<WithMeta<Response>, CohereApiError> {
+        let sent = self.send(route, &payload).await?;
+        let headers = sent.response.headers.clone();
+        let body = sent.response.bytes().await?;
+
+        let mut response = Self::parse_response::<Response>(&body, headers)?;
+        response.meta.attempts = sent.attempts;
+        response.meta.rate_limit_wait = sent.rate_limit_wait;
+        self.report_warnings(&response.meta.warnings);
+        telemetry::record_meta(&response.meta);
+
+        if let (Some(cache), Some(key)) = (&self.cache, cache_key) {
+            let headers = response.meta.headers.clone();
+            cache.insert(key, Cached::Response { body, headers });
+        }
+
+        Ok(response)
+    }
+
+    /// Deserializes a response body along with the metadata found in the body and headers.
+    fn parse_response<Response: DeserializeOwned>(
+        body: &[u8],
+        headers: header::HeaderMap,
+    ) -> Result<WithMeta<Response>, CohereApiError> {
+        let data = serde_json::from_slice::<Response>(body).map_err(|source| {
+            CohereApiError::Deserialization {
+                body: String::from_utf8_lossy(body).into_owned(),
+                request_id: error::request_id(&headers),
+                source,
+            }
+        })?;
+
+        let envelope = serde_json::from_slice::<ResponseEnvelope>(body).unwrap_or_default();
+        let api_meta = envelope.meta.unwrap_or_default();
+
+        let mut warnings = warning::warnings(&headers);
+        warning::extend_with_body_warnings(&mut warnings, api_meta.warnings, &headers);
+
+        Ok(WithMeta {
+            data,
+            meta: ResponseMeta {
+                request_id: envelope.id.or_else(|| error::request_id(&headers)),
+                api_version: api_meta.api_version,
+                billed_units: api_meta.billed_units,
+                headers,
+                warnings,
+                ..Default::default()
+            },
+        })
+    }
+
+    /// Sends the payload to the given route, retrying according to the retry policy,
+    /// and returns the first successful response.
+    async fn send<Request: Serialize>(
+        &self,
+        route: &str,
+        payload: &Request,
+    ) -> Result<Sent, CohereApiError> {
+        let headers = self.request_headers()?;
+
+        let body = Bytes::from(serde_json::to_vec(payload).map_err(CohereApiError::Serialization)?);
+        // middlewares work on a JSON value, only build it when there are some
+        let value = match self.middlewares.is_empty() {
+            true => None,
+            false => Some(serde_json::to_value(payload).map_err(CohereApiError::Serialization)?),
+        };
+
+        let mut attempt = 1;
+        let mut rate_limit_wait = Duration::ZERO;
+        // whether the credentials were refreshed after the API rejected the key
+        let mut refreshed = false;
+        loop {
+            rate_limit_wait += self.rate_limiter.acquire(route).await;
+
+            let mut headers = headers.clone();
+            let api_key = self.authorize(&mut headers)?;
+            let body = match &value {
+                Some(value) => {
+                    self.run_request_middlewares(route, attempt, &mut headers, value.clone())?
+                }
+                None => body.clone(),
+            };
+
+            let result = self.send_to_endpoints(route, attempt, headers, body).await;
+
+            match result {
+                Err(CohereApiError::Unauthorized(_))
+                    if !refreshed
+                        && api_key.is_some_and(|api_key| self.credentials.refresh(&api_key)) =>
+                {
+                    refreshed = true;
+                    attempt += 1;
+                }
+                Err(CohereApiError::Unauthorized(details)) => {
+                    return Err(CohereApiError::InvalidApiKey(Some(details)))
+                }
+                // the retry after a refresh does not count against the retry policy
+                Err(error)
+                    if error.is_retryable()
+                        && attempt - u32::from(refreshed) < self.retry_policy.max_attempts =>
+                {
+                    let backoff = self.retry_policy.backoff(attempt, error.retry_after());
+                    telemetry::record_retry(attempt, backoff, &error);
+                    Delay::new(backoff).await;
+                    attempt += 1;
+                }
+                result => {
+                    return result.map(|response| Sent {
+                        response,
+                        attempts: attempt,
+                        rate_limit_wait,
                     })
-                    .message,
-            ))
+                }
+            }
+        }
+    }
+
+    /// Sends one attempt to the endpoints in order of preference, failing over to the next one
+    /// when an endpoint is unreachable or fails with a server error.
+    async fn send_to_endpoints(
+        &self,
+        route: &str,
+        attempt: u32,
+        headers: header::HeaderMap,
+        body: Bytes,
+    ) -> Result<HttpResponse, CohereApiError> {
+        let mut result = Err(CohereApiError::Unknown);
+        for endpoint in self.endpoints.candidates() {
+            let request = HttpRequest {
+                method: Method::POST,
+                url: endpoint.route_url(route),
+                headers: headers.clone(),
+                body: body.clone(),
+                timeout: self.options.timeout,
+            };
+            let started = Instant::now();
+            result = match self.transport.send(request).await {
+                Ok(response) => Self::check_response(response).await,
+                Err(error) => Err(error),
+            };
+            self.run_response_middlewares(route, attempt, started.elapsed(), &result);
+            telemetry::record_attempt(attempt, &result.as_ref().map(|response| response.status));
+
+            match &result {
+                Err(error) if failover::is_failover(error) => {
+                    endpoint.record_failure(&self.endpoints.policy);
+                    continue;
+                }
+                // e.g. a timeout, which does not tell whether the endpoint is healthy
+                Err(error) if error.details().is_none() => {}
+                _ => endpoint.record_success(),
+            }
+            break;
+        }
+        result
+    }
+
+    fn run_request_middlewares(
+        &self,
+        route: &str,
+        attempt: u32,
+        headers: &mut header::HeaderMap,
+        mut body: serde_json::Value,
+    ) -> Result<Bytes, CohereApiError> {
+        for middleware in self.middlewares.iter() {
+            middleware.on_request(&mut MiddlewareRequest {
+                route,
+                attempt,
+                headers,
+                body: &mut body,
+            })?;
+        }
+
+        Ok(serde_json::to_vec(&body)
+            .map_err(CohereApiError::Serialization)?
+            .into())
+    }
+
+    fn run_response_middlewares(
+        &self,
+        route: &str,
+        attempt: u32,
+        elapsed: Duration,
+        result: &Result<HttpResponse, CohereApiError>,
+    ) {
+        for middleware in self.middlewares.iter().rev() {
+            match result {
+                Ok(response) => middleware.on_response(&MiddlewareResponse {
+                    route,
+                    attempt,
+                    status: response.status,
+                    headers: &response.headers,
+                    elapsed,
+                }),
+                Err(error) => middleware.on_error(route, attempt, error),
+            }
+        }
+    }
+
+    async fn check_response(response: HttpResponse) -> Result<HttpResponse, CohereApiError> {
+        let status = response.status;
+        if !status.is_success() {
+            let headers = response.headers.clone();
+            let body = String::from_utf8_lossy(&response.bytes().await?).into_owned();
+            Err(CohereApiError::from_response(status, &headers, body))
         } else {
-            Ok(response.json::<Response>().await?)
+            Ok(response)
+        }
+    }
+
+    /// Runs the future until it completes, the cancellation token of the options is cancelled or
+    /// the timeout of the options elapses.
+    async fn cancellable<T, F: Future<Output = Result<T, CohereApiError>>>(
+        &self,
+        future: F,
+    ) -> Result<T, CohereApiError> {
+        if self.options.cancellation.is_none() && self.options.timeout.is_none() {
+            return future.await;
+        }
+
+        let cancelled = async {
+            match &self.options.cancellation {
+                Some(token) => token.cancelled().await,
+                None => pending().await,
+            }
+        };
+        let deadline = async {
+            match self.options.timeout {
+                Some(timeout) => Delay::new(timeout).await,
+                None => pending().await,
+            }
+        };
+        futures_util::pin_mut!(future, cancelled, deadline);
+        match select(future, select(cancelled, deadline)).await {
+            Either::Left((result, _)) => result,
+            Either::Right((Either::Left(_), _)) => Err(CohereApiError::Cancelled),
+            Either::Right((Either::Right(_), _)) => Err(CohereApiError::Timeout),
+        }
+    }
+
+    /// The client headers combined with the ones of the request options.
+    fn request_headers(&self) -> Result<header::HeaderMap, CohereApiError> {
+        let mut headers = self.headers.clone();
+        for (name, value) in &self.options.headers {
+            headers.insert(name.clone(), value.clone());
+        }
+
+        if let Some(key) = &self.options.idempotency_key {
+            let value = header::HeaderValue::from_str(key)
+                .map_err(|_| CohereApiError::InvalidOption("idempotency key".to_string()))?;
+            headers.insert("Idempotency-Key", value);
+        }
+
+        if let Some(api_key) = &self.options.api_key {
+            let authorization = credentials::bearer(api_key)
+                .map_err(|_| CohereApiError::InvalidOption("API key".to_string()))?;
+            headers.insert(header::AUTHORIZATION, authorization);
+        }
+
+        Ok(headers)
+    }
+
+    /// Sets the authorization header from the credential provider, unless the request options
+    /// override the API key, and returns the key used.
+    fn authorize(&self, headers: &mut header::HeaderMap) -> Result<Option<String>, CohereApiError> {
+        if self.options.api_key.is_some() {
+            return Ok(None);
+        }
+
+        let api_key = self.credentials.api_key()?;
+        let authorization = credentials::bearer(&api_key)
+            .map_err(|error| CohereApiError::Credentials(Box::new(error)))?;
+        headers.insert(header::AUTHORIZATION, authorization);
+        Ok(Some(api_key))
+    }
+
+    fn report_warnings(&self, warnings: &[ApiWarning]) {
+        match &self.warning_handler {
+            Some(handler) => warnings.iter().for_each(|warning| handler(warning)),
+            None => telemetry::warnings(warnings),
         }
     }
 
@@ -140,9 +580,9 @@ impl Cohere {
             .request::<(), CohereCheckApiKeyResponse>("check-api-key", ())
             .await?;
 
-        match response.valid {
+        match response.data.valid {
             true => Ok(()),
-            false => Err(CohereApiError::InvalidApiKey),
+            false => Err(CohereApiError::InvalidApiKey(None)),
         }
     }
 
@@ -151,11 +591,19 @@ impl Cohere {
         &self,
         request: &GenerateRequest<'input>,
     ) -> Result<Vec<Generation>, CohereApiError> {
+        Ok(self.generate_with_meta(request).await?.data)
+    }
+
+    /// Same as [`Cohere::generate`], also returning the response metadata.
+    pub async fn generate_with_meta<'input>(
+        &self,
+        request: &GenerateRequest<'input>,
+    ) -> Result<WithMeta<Vec<Generation>>, CohereApiError> {
         let response = self
             .request::<_, GenerateResponse>("generate", request)
             .await?;
 
-        Ok(response.generations)
+        Ok(response.map(|response| response.generations))
     }
 
     /// Chat with Cohere's LLM
@@ -163,35 +611,96 @@ impl Cohere {
         &self,
         request: &ChatRequest<'input>,
     ) -> Result<ChatResponse, CohereApiError> {
-        let response = self.request::<_, ChatResponse>("chat", request).await?;
+        Ok(self.chat_with_meta(request).await?.data)
+    }
 
-        Ok(response)
+    /// Same as [`Cohere::chat`], also returning the response metadata.
+    pub async fn chat_with_meta<'input>(
+        &self,
+        request: &ChatRequest<'input>,
+    ) -> Result<WithMeta<ChatResponse>, CohereApiError> {
+        self.request::<_, ChatResponse>("chat", request).await
     }
 
     /// Chat with Cohere's LLM
     pub async fn chat_stream<'input>(
         &self,
         request: &ChatStreamRequest<'input>,
-    ) -> Result<Receiver<Result<StreamEvent, CohereStreamError>>, CohereApiError> {
-        let url = Url::parse(&format!("{}/chat", self.api_url)).expect("api url should be valid");
-        let mut response = self.client.post(url).json(&request).send().await?;
-        let (tx, rx) = channel::<Result<StreamEvent, CohereStreamError>>(32);
-        tokio::spawn(async move {
-            while let Ok(Some(chunk)) = response.chunk().await {
-                println!("{:?}\n", chunk);
-                let event = serde_json::from_slice::<StreamEvent>(&chunk);
-                let end = event
-                    .as_ref()
-                    .is_ok_and(|e| matches!(e, StreamEvent::End { .. }));
-                tx.send(event.map_err(|e| CohereStreamError::from(e)))
-                    .await
-                    .expect("Failed to send message to channel");
-                if end {
-                    break;
-                }
-            }
-        });
-        Ok(rx)
+    ) -> Result<ChatStream, CohereApiError> {
+        request.validate().map_err(CohereApiError::Validation)?;
+
+        let model = self.request_model(request);
+        let span = telemetry::request_span("chat", model.as_deref());
+        let started = Instant::now();
+        // the timeout of the options bounds the wait for the response, not the reading of the
+        // events, so it is not passed on to the transport
+        let sender = Cohere {
+            options: RequestOptions {
+                timeout: None,
+                ..self.options.clone()
+            },
+            ..self.clone()
+        };
+        let result =
+            telemetry::instrument(&span, self.cancellable(sender.send("chat", request))).await;
+
+        let mut metrics = StreamMetrics::new(self.metrics.clone(), model, started);
+        let Sent { response, .. } =
+            result.inspect_err(|error| metrics.record(None, Some(error)))?;
+        self.report_warnings(&warning::warnings(&response.headers));
+
+        // `None` marks the end of the body, to decode an event not followed by a newline
+        let chunks = response
+            .body
+            .map(Some)
+            .chain(futures_util::stream::once(ready(None)));
+        let events = chunks
+            .scan(
+                (EventDecoder::default(), false),
+                move |(decoder, ended), chunk| {
+                    if *ended {
+                        return ready(None);
+                    }
+                    let chunk_is_last = chunk.is_none();
+                    let events = match chunk {
+                        Some(Ok(chunk)) => decoder.push(&chunk),
+                        Some(Err(error)) => {
+                            *ended = true;
+                            metrics.record(None, Some(&error));
+                            return ready(Some(vec![Err(CohereStreamError::Transport(error))]));
+                        }
+                        None => decoder.finish(),
+                    };
+
+                    let mut results = Vec::with_capacity(events.len());
+                    for event in events {
+                        telemetry::stream_event(&span, &event);
+                        if let Ok(StreamEvent::End { response, .. }) = &event {
+                            *ended = true;
+                            let billed_units = response
+                                .meta
+                                .as_ref()
+                                .and_then(|meta| meta.billed_units.as_ref());
+                            metrics.record(billed_units, None);
+                        }
+                        results.push(event.map_err(CohereStreamError::from));
+                        if *ended {
+                            break;
+                        }
+                    }
+                    if chunk_is_last {
+                        // the body ended without a `stream-end` event
+                        metrics.record(None, None);
+                    }
+                    ready(Some(results))
+                },
+            )
+            .flat_map(futures_util::stream::iter);
+
+        Ok(ChatStream::new(match &self.options.cancellation {
+            Some(token) => events.take_until(token.cancelled()).boxed(),
+            None => events.boxed(),
+        }))
     }
 
     /// Returns text embeddings.
@@ -201,9 +710,131 @@ impl Cohere {
         &self,
         request: &EmbedRequest<'input>,
     ) -> Result<Vec<Vec<f64>>, CohereApiError> {
-        let response = self.request::<_, EmbedResponse>("embed", request).await?;
+        Ok(self.embed_with_meta(request).await?.data)
+    }
+
+    /// Same as [`Cohere::embed`], also returning the response metadata.
+    pub async fn embed_with_meta<'input>(
+        &self,
+        request: &EmbedRequest<'input>,
+    ) -> Result<WithMeta<Vec<Vec<f64>>>, CohereApiError> {
+        if self.cache.is_none() && self.embedding_store.is_none() {
+            let response = self.request::<_, EmbedResponse>("embed", request).await?;
+            return Ok(response.map(|response| response.embeddings));
+        }
+        request.validate().map_err(CohereApiError::Validation)?;
+
+        // each text is cached on its own, in memory keyed by the request it would be sent in alone
+        let keys = request
+            .texts
+            .iter()
+            .map(|text| {
+                let cache_key = match &self.cache {
+                    Some(_) => Some(ResponseCache::key(
+                        "embed",
+                        &EmbedRequest {
+                            texts: std::slice::from_ref(text),
+                            ..request.clone()
+                        },
+                    )?),
+                    None => None,
+                };
+                let store_key = self
+                    .embedding_store
+                    .as_ref()
+                    .and_then(|_| EmbeddingStore::key(request, text));
+                Ok((cache_key, store_key))
+            })
+            .collect::<Result<Vec<_>, CohereApiError>>()?;
+        let mut embeddings: Vec<Option<Vec<f64>>> = keys
+            .iter()
+            .map(|(cache_key, store_key)| self.cached_embedding(cache_key, store_key))
+            .collect();
+
+        let missing: Vec<String> = request
+            .texts
+            .iter()
+            .zip(&embeddings)
+            .filter(|(_, embedding)| embedding.is_none())
+            .map(|(text, _)| text.clone())
+            .collect();
+        if missing.is_empty() {
+            return Ok(WithMeta {
+                data: embeddings.into_iter().flatten().collect(),
+                meta: ResponseMeta {
+                    cached: true,
+                    ..Default::default()
+                },
+            });
+        }
+
+        let response = self
+            .request::<_, EmbedResponse>(
+                "embed",
+                &EmbedRequest {
+                    texts: &missing,
+                    ..request.clone()
+                },
+            )
+            .await?;
+        if response.data.embeddings.len() != missing.len() {
+            return Err(CohereApiError::Unknown);
+        }
+
+        let mut fetched = response.data.embeddings.into_iter();
+        let mut stored = Vec::new();
+        for ((cache_key, store_key), embedding) in keys.into_iter().zip(embeddings.iter_mut()) {
+            if embedding.is_some() {
+                continue;
+            }
+            let fetched = fetched.next().unwrap_or_default();
+            if let (Some(cache), Some(key)) = (&self.cache, cache_key) {
+                cache.insert(key, Cached::Embedding(fetched.clone()));
+            }
+            if let Some(key) = store_key {
+                stored.push((key, fetched.clone()));
+            }
+            *embedding = Some(fetched);
+        }
+        let mut meta = response.meta;
+        if let Some(store) = self.embedding_store.as_ref().filter(|_| !stored.is_empty()) {
+            // the embeddings were paid for, failing to persist them does not fail the call
+            if let Err(error) = store.insert(stored) {
+                let warning = ApiWarning {
+                    message: format!(
+                        "failed to write to the embedding store `{}`: {error}",
+                        store.path().display()
+                    ),
+                    kind: WarningKind::Storage,
+                };
+                self.report_warnings(std::slice::from_ref(&warning));
+                meta.warnings.push(warning);
+            }
+        }
+
+        Ok(WithMeta {
+            data: embeddings.into_iter().flatten().collect(),
+            meta,
+        })
+    }
+
+    /// Looks a text up in the in-memory cache, then in the embedding store.
+    fn cached_embedding(
+        &self,
+        cache_key: &Option<String>,
+        store_key: &Option<String>,
+    ) -> Option<Vec<f64>> {
+        if let (Some(cache), Some(key)) = (&self.cache, cache_key) {
+            if let Some(Cached::Embedding(embedding)) = cache.get(key) {
+                return Some(embedding);
+            }
+        }
 
-        Ok(response.embeddings)
+        let embedding = self.embedding_store.as_ref()?.get(store_key.as_ref()?)?;
+        if let (Some(cache), Some(key)) = (&self.cache, cache_key) {
+            cache.insert(key.clone(), Cached::Embedding(embedding.clone()));
+        }
+        Some(embedding)
     }
 
     /// Makes a prediction about which label fits the specified text inputs best.
@@ -212,11 +843,19 @@ impl Cohere {
         &self,
         request: &ClassifyRequest<'input>,
     ) -> Result<Vec<Classification>, CohereApiError> {
+        Ok(self.classify_with_meta(request).await?.data)
+    }
+
+    /// Same as [`Cohere::classify`], also returning the response metadata.
+    pub async fn classify_with_meta<'input>(
+        &self,
+        request: &ClassifyRequest<'input>,
+    ) -> Result<WithMeta<Vec<Classification>>, CohereApiError> {
         let response = self
             .request::<_, ClassifyResponse>("classify", request)
             .await?;
 
-        Ok(response.classifications)
+        Ok(response.map(|response| response.classifications))
     }
 
     /// Generates a summary in English for a given text.
@@ -224,11 +863,19 @@ impl Cohere {
         &self,
         request: &SummarizeRequest<'input>,
     ) -> Result<String, CohereApiError> {
+        Ok(self.summarize_with_meta(request).await?.data)
+    }
+
+    /// Same as [`Cohere::summarize`], also returning the response metadata.
+    pub async fn summarize_with_meta<'input>(
+        &self,
+        request: &SummarizeRequest<'input>,
+    ) -> Result<WithMeta<String>, CohereApiError> {
         let response = self
             .request::<_, SummarizeResponse>("summarize", request)
             .await?;
 
-        Ok(response.summary)
+        Ok(response.map(|response| response.summary))
     }
 
     /// Splits input text into smaller units called tokens using byte-pair encoding (BPE).
@@ -236,9 +883,15 @@ impl Cohere {
         &self,
         request: &TokenizeRequest<'input>,
     ) -> Result<TokenizeResponse, CohereApiError> {
-        let response = self.request("tokenize", request).await?;
+        Ok(self.tokenize_with_meta(request).await?.data)
+    }
 
-        Ok(response)
+    /// Same as [`Cohere::tokenize`], also returning the response metadata.
+    pub async fn tokenize_with_meta<'input>(
+        &self,
+        request: &TokenizeRequest<'input>,
+    ) -> Result<WithMeta<TokenizeResponse>, CohereApiError> {
+        self.request("tokenize", request).await
     }
 
     /// Takes tokens using byte-pair encoding and returns their text representation.
@@ -246,11 +899,19 @@ impl Cohere {
         &self,
         request: &DetokenizeRequest<'input>,
     ) -> Result<String, CohereApiError> {
+        Ok(self.detokenize_with_meta(request).await?.data)
+    }
+
+    /// Same as [`Cohere::detokenize`], also returning the response metadata.
+    pub async fn detokenize_with_meta<'input>(
+        &self,
+        request: &DetokenizeRequest<'input>,
+    ) -> Result<WithMeta<String>, CohereApiError> {
         let response = self
             .request::<_, DetokenizeResponse>("detokenize", request)
             .await?;
 
-        Ok(response.text)
+        Ok(response.map(|response| response.text))
     }
 
     /// Identifies which language each of the provided texts is written in
@@ -258,11 +919,19 @@ impl Cohere {
         &self,
         request: &DetectLanguageRequest<'input>,
     ) -> Result<Vec<DetectLanguageResult>, CohereApiError> {
+        Ok(self.detect_language_with_meta(request).await?.data)
+    }
+
+    /// Same as [`Cohere::detect_language`], also returning the response metadata.
+    pub async fn detect_language_with_meta<'input>(
+        &self,
+        request: &DetectLanguageRequest<'input>,
+    ) -> Result<WithMeta<Vec<DetectLanguageResult>>, CohereApiError> {
         let response = self
             .request::<_, DetectLanguageResponse>("detect-language", request)
             .await?;
 
-        Ok(response.results)
+        Ok(response.map(|response| response.results))
     }
 
     /// Takes a query plus an list of texts and return an ordered array with each text assigned a relevance score.
@@ -270,8 +939,16 @@ impl Cohere {
         &self,
         request: &RerankRequest<'input>,
     ) -> Result<Vec<RerankResult>, CohereApiError> {
+        Ok(self.rerank_with_meta(request).await?.data)
+    }
+
+    /// Same as [`Cohere::rerank`], also returning the response metadata.
+    pub async fn rerank_with_meta<'input>(
+        &self,
+        request: &RerankRequest<'input>,
+    ) -> Result<WithMeta<Vec<RerankResult>>, CohereApiError> {
         let response = self.request::<_, RerankResponse>("rerank", request).await?;
 
-        Ok(response.results)
+        Ok(response.map(|response| response.results))
     }
 }
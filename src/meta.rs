@@ -0,0 +1,79 @@
+use std::time::Duration;
+
+use reqwest::header;
+use serde::Deserialize;
+
+use crate::ApiWarning;
+
+/// A response payload together with the metadata of the call that produced it.
+#[derive(Debug)]
+pub struct WithMeta<T> {
+    pub data: T,
+    pub meta: ResponseMeta,
+}
+
+impl<T> WithMeta<T> {
+    pub fn map<U, F: FnOnce(T) -> U>(self, f: F) -> WithMeta<U> {
+        WithMeta {
+            data: f(self.data),
+            meta: self.meta,
+        }
+    }
+}
+
+/// Metadata describing how a call was served.
+#[derive(Debug, Clone, Default)]
+pub struct ResponseMeta {
+    /// Identifier of the request, useful when contacting Cohere support.
+    pub request_id: Option<String>,
+    /// The API version that served the request.
+    pub api_version: Option<ApiVersion>,
+    /// The units the request was billed for.
+    pub billed_units: Option<BilledUnits>,
+    /// The raw response headers.
+    pub headers: header::HeaderMap,
+    /// Warnings returned by the API, from the response headers and body.
+    pub warnings: Vec<ApiWarning>,
+    /// Number of attempts made, including retries.
+    pub attempts: u32,
+    /// Time spent waiting for the client-side rate limiter.
+    pub rate_limit_wait: Duration,
+    /// Whether the response was served from the client cache, without calling the API.
+    pub cached: bool,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct ApiVersion {
+    pub version: String,
+    #[serde(default)]
+    pub is_deprecated: bool,
+    #[serde(default)]
+    pub is_experimental: bool,
+}
+
+#[derive(Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct BilledUnits {
+    /// The number of billed input tokens.
+    pub input_tokens: Option<f64>,
+    /// The number of billed output tokens.
+    pub output_tokens: Option<f64>,
+    /// The number of billed search units.
+    pub search_units: Option<f64>,
+    /// The number of billed classification units.
+    pub classifications: Option<f64>,
+}
+
+/// The fields shared by the bodies of most API responses.
+#[derive(Deserialize, Debug, Default)]
+pub(crate) struct ResponseEnvelope {
+    pub id: Option<String>,
+    pub meta: Option<ApiMeta>,
+}
+
+#[derive(Deserialize, Debug, Default)]
+pub(crate) struct ApiMeta {
+    pub api_version: Option<ApiVersion>,
+    pub billed_units: Option<BilledUnits>,
+    #[serde(default)]
+    pub warnings: Vec<String>,
+}
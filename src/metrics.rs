@@ -0,0 +1,336 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use crate::{BilledUnits, CohereApiError};
+
+/// Upper bounds, in seconds, of the buckets of the latency histograms.
+pub const LATENCY_BUCKETS: [f64; 12] = [
+    0.05,
+    0.1,
+    0.25,
+    0.5,
+    1.0,
+    2.5,
+    5.0,
+    10.0,
+    30.0,
+    60.0,
+    120.0,
+    f64::INFINITY,
+];
+
+/// Receives a report of every call made by the client, see [`CohereBuilder::metrics`](crate::CohereBuilder::metrics).
+///
+/// [`InMemoryMetrics`] keeps totals that can be inspected in tests, and with the `prometheus`
+/// feature `PrometheusMetrics` renders them in the Prometheus text format.
+pub trait Metrics: Send + Sync {
+    fn record(&self, call: &CallMetrics<'_>);
+}
+
+impl<T: Metrics + ?Sized> Metrics for Arc<T> {
+    fn record(&self, call: &CallMetrics<'_>) {
+        (**self).record(call)
+    }
+}
+
+/// A finished call, as reported to [`Metrics::record`].
+#[derive(Debug)]
+pub struct CallMetrics<'a> {
+    /// The API route, e.g. `"embed"`.
+    pub route: &'a str,
+    /// The model of the request, when it set one.
+    pub model: Option<&'a str>,
+    /// Time spent on the call, including retries and rate limiting.
+    /// For a streamed chat, time until the stream ended.
+    pub latency: Duration,
+    /// The units billed for the call, when the response reported them.
+    pub billed_units: Option<&'a BilledUnits>,
+    /// The error the call failed with.
+    pub error: Option<&'a CohereApiError>,
+}
+
+/// Reports a streamed chat once its stream ends, with the units billed in its `stream-end` event.
+/// A stream dropped before its end is not reported.
+pub(crate) struct StreamMetrics {
+    metrics: Option<Arc<dyn Metrics>>,
+    model: Option<String>,
+    started: Instant,
+}
+
+impl StreamMetrics {
+    pub(crate) fn new(
+        metrics: Option<Arc<dyn Metrics>>,
+        model: Option<String>,
+        started: Instant,
+    ) -> Self {
+        Self {
+            metrics,
+            model,
+            started,
+        }
+    }
+
+    /// Records the call, only the first time it is called.
+    pub(crate) fn record(
+        &mut self,
+        billed_units: Option<&BilledUnits>,
+        error: Option<&CohereApiError>,
+    ) {
+        if let Some(metrics) = self.metrics.take() {
+            metrics.record(&CallMetrics {
+                route: "chat",
+                model: self.model.as_deref(),
+                latency: self.started.elapsed(),
+                billed_units,
+                error,
+            });
+        }
+    }
+}
+
+/// Totals of the calls made to a route with a model.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RouteMetrics {
+    pub route: String,
+    pub model: Option<String>,
+    pub requests: u64,
+    /// Number of failed calls by [`CohereApiError::kind`].
+    pub errors: BTreeMap<&'static str, u64>,
+    /// Number of calls per bucket of [`LATENCY_BUCKETS`], not cumulative.
+    pub latency_buckets: [u64; LATENCY_BUCKETS.len()],
+    pub latency_sum: Duration,
+    pub input_tokens: f64,
+    pub output_tokens: f64,
+    pub search_units: f64,
+    pub classifications: f64,
+}
+
+impl RouteMetrics {
+    fn record(&mut self, call: &CallMetrics<'_>) {
+        self.requests += 1;
+        if let Some(error) = call.error {
+            *self.errors.entry(error.kind()).or_default() += 1;
+        }
+
+        let latency = call.latency.as_secs_f64();
+        let bucket = LATENCY_BUCKETS
+            .iter()
+            .position(|bound| latency <= *bound)
+            .unwrap_or(LATENCY_BUCKETS.len() - 1);
+        self.latency_buckets[bucket] += 1;
+        self.latency_sum += call.latency;
+
+        if let Some(billed_units) = call.billed_units {
+            self.input_tokens += billed_units.input_tokens.unwrap_or_default();
+            self.output_tokens += billed_units.output_tokens.unwrap_or_default();
+            self.search_units += billed_units.search_units.unwrap_or_default();
+            self.classifications += billed_units.classifications.unwrap_or_default();
+        }
+    }
+
+    /// Total number of failed calls.
+    pub fn error_count(&self) -> u64 {
+        self.errors.values().sum()
+    }
+}
+
+/// [`Metrics`] keeping per route and model totals in memory.
+///
+/// ```no_run
+/// use std::sync::Arc;
+/// use cohere_rust::{Cohere, InMemoryMetrics};
+///
+/// let metrics = Arc::new(InMemoryMetrics::new());
+/// let co = Cohere::builder().metrics(metrics.clone()).build();
+/// // ...
+/// for route in metrics.snapshot() {
+///     println!("{} {:?}: {} requests", route.route, route.model, route.requests);
+/// }
+/// ```
+#[derive(Debug, Default)]
+pub struct InMemoryMetrics {
+    routes: Mutex<HashMap<(String, Option<String>), RouteMetrics>>,
+}
+
+impl InMemoryMetrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The totals recorded so far, sorted by route and model.
+    pub fn snapshot(&self) -> Vec<RouteMetrics> {
+        let routes = self.routes.lock().expect("metrics lock poisoned");
+        let mut snapshot: Vec<RouteMetrics> = routes.values().cloned().collect();
+        snapshot.sort_by(|a, b| (&a.route, &a.model).cmp(&(&b.route, &b.model)));
+        snapshot
+    }
+
+    /// The totals of a route, across all models.
+    pub fn route(&self, route: &str) -> Option<RouteMetrics> {
+        let routes = self.routes.lock().expect("metrics lock poisoned");
+        routes
+            .values()
+            .filter(|metrics| metrics.route == route)
+            .cloned()
+            .reduce(|mut total, metrics| {
+                total.model = None;
+                total.requests += metrics.requests;
+                for (kind, count) in metrics.errors {
+                    *total.errors.entry(kind).or_default() += count;
+                }
+                for (bucket, count) in metrics.latency_buckets.iter().enumerate() {
+                    total.latency_buckets[bucket] += count;
+                }
+                total.latency_sum += metrics.latency_sum;
+                total.input_tokens += metrics.input_tokens;
+                total.output_tokens += metrics.output_tokens;
+                total.search_units += metrics.search_units;
+                total.classifications += metrics.classifications;
+                total
+            })
+    }
+}
+
+impl Metrics for InMemoryMetrics {
+    fn record(&self, call: &CallMetrics<'_>) {
+        let mut routes = self.routes.lock().expect("metrics lock poisoned");
+        routes
+            .entry((call.route.to_string(), call.model.map(str::to_string)))
+            .or_insert_with(|| RouteMetrics {
+                route: call.route.to_string(),
+                model: call.model.map(str::to_string),
+                ..Default::default()
+            })
+            .record(call);
+    }
+}
+
+/// [`Metrics`] rendered in the Prometheus text exposition format, e.g. from a `/metrics` handler.
+///
+/// Exposes `cohere_requests_total`, `cohere_errors_total`, the `cohere_request_duration_seconds`
+/// histogram and `cohere_billed_units_total`, labelled by route and model.
+#[cfg(feature = "prometheus")]
+#[derive(Debug, Default)]
+pub struct PrometheusMetrics {
+    metrics: InMemoryMetrics,
+}
+
+#[cfg(feature = "prometheus")]
+impl PrometheusMetrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The metrics recorded so far, in the Prometheus text exposition format.
+    pub fn render(&self) -> String {
+        use std::fmt::Write;
+
+        let snapshot = self.metrics.snapshot();
+        let mut output = String::new();
+        let labels = |metrics: &RouteMetrics| {
+            format!(
+                "route=\"{}\",model=\"{}\"",
+                escape(&metrics.route),
+                escape(metrics.model.as_deref().unwrap_or_default())
+            )
+        };
+
+        output.push_str("# HELP cohere_requests_total Calls made to the Cohere API.\n");
+        output.push_str("# TYPE cohere_requests_total counter\n");
+        for metrics in &snapshot {
+            let _ = writeln!(
+                output,
+                "cohere_requests_total{{{}}} {}",
+                labels(metrics),
+                metrics.requests
+            );
+        }
+
+        output.push_str("# HELP cohere_errors_total Failed calls to the Cohere API.\n");
+        output.push_str("# TYPE cohere_errors_total counter\n");
+        for metrics in &snapshot {
+            for (kind, count) in &metrics.errors {
+                let _ = writeln!(
+                    output,
+                    "cohere_errors_total{{{},kind=\"{kind}\"}} {count}",
+                    labels(metrics)
+                );
+            }
+        }
+
+        output.push_str(
+            "# HELP cohere_request_duration_seconds Latency of the calls to the Cohere API.\n",
+        );
+        output.push_str("# TYPE cohere_request_duration_seconds histogram\n");
+        for metrics in &snapshot {
+            let mut cumulative = 0;
+            for (bound, count) in LATENCY_BUCKETS.iter().zip(metrics.latency_buckets) {
+                cumulative += count;
+                let bound = match bound.is_infinite() {
+                    true => "+Inf".to_string(),
+                    false => bound.to_string(),
+                };
+                let _ = writeln!(
+                    output,
+                    "cohere_request_duration_seconds_bucket{{{},le=\"{bound}\"}} {cumulative}",
+                    labels(metrics)
+                );
+            }
+            let _ = writeln!(
+                output,
+                "cohere_request_duration_seconds_sum{{{}}} {}",
+                labels(metrics),
+                metrics.latency_sum.as_secs_f64()
+            );
+            let _ = writeln!(
+                output,
+                "cohere_request_duration_seconds_count{{{}}} {}",
+                labels(metrics),
+                metrics.requests
+            );
+        }
+
+        output.push_str("# HELP cohere_billed_units_total Units billed by the Cohere API.\n");
+        output.push_str("# TYPE cohere_billed_units_total counter\n");
+        for metrics in &snapshot {
+            for (unit, value) in [
+                ("input_tokens", metrics.input_tokens),
+                ("output_tokens", metrics.output_tokens),
+                ("search_units", metrics.search_units),
+                ("classifications", metrics.classifications),
+            ] {
+                let _ = writeln!(
+                    output,
+                    "cohere_billed_units_total{{{},unit=\"{unit}\"}} {value}",
+                    labels(metrics)
+                );
+            }
+        }
+
+        output
+    }
+
+    /// The totals recorded so far, see [`InMemoryMetrics::snapshot`].
+    pub fn snapshot(&self) -> Vec<RouteMetrics> {
+        self.metrics.snapshot()
+    }
+}
+
+#[cfg(feature = "prometheus")]
+impl Metrics for PrometheusMetrics {
+    fn record(&self, call: &CallMetrics<'_>) {
+        self.metrics.record(call)
+    }
+}
+
+/// Escapes a label value of the Prometheus text format.
+#[cfg(feature = "prometheus")]
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
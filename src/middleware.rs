@@ -0,0 +1,56 @@
+use std::time::Duration;
+
+use reqwest::{header, StatusCode};
+use serde_json::Value;
+
+use crate::CohereApiError;
+
+/// Hooks run around every attempt of every call, including the initial request of
+/// [`Cohere::chat_stream`](crate::Cohere::chat_stream).
+///
+/// Middlewares are registered with [`CohereBuilder::middleware`](crate::CohereBuilder::middleware).
+/// [`Middleware::on_request`] runs in registration order, [`Middleware::on_response`] and
+/// [`Middleware::on_error`] in reverse order.
+pub trait Middleware: Send + Sync {
+    /// Inspects or modifies the outgoing request. Returning an error aborts the call.
+    fn on_request(&self, request: &mut MiddlewareRequest<'_>) -> Result<(), CohereApiError> {
+        let _ = request;
+        Ok(())
+    }
+
+    /// Observes a successful response, before its body is read.
+    fn on_response(&self, response: &MiddlewareResponse<'_>) {
+        let _ = response;
+    }
+
+    /// Observes a failed attempt. The attempt may still be retried.
+    fn on_error(&self, route: &str, attempt: u32, error: &CohereApiError) {
+        let _ = (route, attempt, error);
+    }
+}
+
+/// An outgoing request, as seen by [`Middleware::on_request`].
+#[derive(Debug)]
+pub struct MiddlewareRequest<'a> {
+    /// The API route, e.g. `"chat"`.
+    pub route: &'a str,
+    /// The attempt number, starting at 1.
+    pub attempt: u32,
+    /// The request headers. The authorization header is marked as sensitive.
+    pub headers: &'a mut header::HeaderMap,
+    /// The JSON payload.
+    pub body: &'a mut Value,
+}
+
+/// A successful response, as seen by [`Middleware::on_response`].
+#[derive(Debug)]
+pub struct MiddlewareResponse<'a> {
+    /// The API route, e.g. `"chat"`.
+    pub route: &'a str,
+    /// The attempt number, starting at 1.
+    pub attempt: u32,
+    pub status: StatusCode,
+    pub headers: &'a header::HeaderMap,
+    /// Time until the response headers were received.
+    pub elapsed: Duration,
+}
@@ -0,0 +1,176 @@
+use std::{
+    collections::HashMap,
+    future::Future,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    task::{Context, Poll, Waker},
+    time::Duration,
+};
+
+use reqwest::header;
+
+/// Options applied to the calls made through [`Cohere::with_options`](crate::Cohere::with_options),
+/// on top of the client-wide configuration.
+///
+/// ```no_run
+/// # async fn run(co: cohere_rust::Cohere, request: cohere_rust::api::rerank::RerankRequest<'_>) {
+/// use std::time::Duration;
+/// use cohere_rust::RequestOptions;
+///
+/// let results = co
+///     .with_options(RequestOptions::new().timeout(Duration::from_secs(2)))
+///     .rerank(&request)
+///     .await;
+/// # }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct RequestOptions {
+    pub(crate) timeout: Option<Duration>,
+    pub(crate) headers: header::HeaderMap,
+    pub(crate) idempotency_key: Option<String>,
+    pub(crate) api_key: Option<String>,
+    pub(crate) cancellation: Option<CancellationToken>,
+}
+
+impl RequestOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Timeout of the whole call, including retries and the delays between them. Each attempt is
+    /// also bounded by it, replacing the timeout of the client. For a streamed chat, it only
+    /// bounds the wait for the response: the events are then read without this timeout.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Extra header sent with the request, replacing any client-wide header with the same name.
+    pub fn header(mut self, name: header::HeaderName, value: header::HeaderValue) -> Self {
+        self.headers.insert(name, value);
+        self
+    }
+
+    /// Value of the `Idempotency-Key` header, sent unchanged with every retry of the request.
+    pub fn idempotency_key<K: Into<String>>(mut self, key: K) -> Self {
+        self.idempotency_key = Some(key.into());
+        self
+    }
+
+    /// API key used instead of the one of the client.
+    pub fn api_key<K: Into<String>>(mut self, api_key: K) -> Self {
+        self.api_key = Some(api_key.into());
+        self
+    }
+
+    /// Abort the request, including any pending retry, once the token is cancelled.
+    pub fn cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation = Some(token);
+        self
+    }
+}
+
+/// Token used to cancel in-flight requests. Clones share the same state.
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken {
+    state: Arc<CancellationState>,
+}
+
+#[derive(Debug, Default)]
+struct CancellationState {
+    cancelled: AtomicBool,
+    wakers: Mutex<Wakers>,
+}
+
+/// The wakers of the pending [`Cancelled`] futures, each removed when its future is dropped.
+#[derive(Debug, Default)]
+struct Wakers {
+    next_key: u64,
+    wakers: HashMap<u64, Waker>,
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cancels every request using this token.
+    pub fn cancel(&self) {
+        self.state.cancelled.store(true, Ordering::SeqCst);
+        let wakers = std::mem::take(
+            &mut self
+                .state
+                .wakers
+                .lock()
+                .expect("waker lock poisoned")
+                .wakers,
+        );
+        for waker in wakers.into_values() {
+            waker.wake();
+        }
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.state.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Completes once the token is cancelled.
+    pub fn cancelled(&self) -> Cancelled {
+        Cancelled {
+            token: self.clone(),
+            key: None,
+        }
+    }
+}
+
+/// Future returned by [`CancellationToken::cancelled`].
+#[derive(Debug)]
+pub struct Cancelled {
+    token: CancellationToken,
+    /// The key of the waker registered in the token, once polled.
+    key: Option<u64>,
+}
+
+impl Future for Cancelled {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        let this = self.get_mut();
+        if this.token.is_cancelled() {
+            return Poll::Ready(());
+        }
+
+        let mut wakers = this.token.state.wakers.lock().expect("waker lock poisoned");
+        let key = *this.key.get_or_insert_with(|| {
+            wakers.next_key += 1;
+            wakers.next_key
+        });
+        match wakers.wakers.get_mut(&key) {
+            Some(waker) if waker.will_wake(cx.waker()) => {}
+            Some(waker) => waker.clone_from(cx.waker()),
+            None => {
+                wakers.wakers.insert(key, cx.waker().clone());
+            }
+        }
+        drop(wakers);
+
+        // the token may have been cancelled while the waker was registered
+        if this.token.is_cancelled() {
+            Poll::Ready(())
+        } else {
+            Poll::Pending
+        }
+    }
+}
+
+impl Drop for Cancelled {
+    fn drop(&mut self) {
+        if let Some(key) = self.key {
+            let mut wakers = self.token.state.wakers.lock().expect("waker lock poisoned");
+            wakers.wakers.remove(&key);
+        }
+    }
+}
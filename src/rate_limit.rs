@@ -0,0 +1,127 @@
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use futures_timer::Delay;
+
+/// Maximum number of requests allowed over a period of time.
+/// Calls above the limit are queued until enough capacity is available.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RateLimit {
+    /// Number of requests allowed per period. This is also the largest burst allowed.
+    pub requests: u32,
+    /// Length of the period.
+    pub per: Duration,
+}
+
+impl RateLimit {
+    pub fn per_second(requests: u32) -> Self {
+        Self {
+            requests,
+            per: Duration::from_secs(1),
+        }
+    }
+
+    pub fn per_minute(requests: u32) -> Self {
+        Self {
+            requests,
+            per: Duration::from_secs(60),
+        }
+    }
+}
+
+/// How much a rate limited route has been throttled so far.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct RateLimitStats {
+    /// Number of calls that went through the limiter.
+    pub calls: u64,
+    /// Number of calls that had to wait for capacity.
+    pub delayed_calls: u64,
+    /// Total time spent waiting, across all calls.
+    pub total_wait: Duration,
+    /// Time the most recent call spent waiting.
+    pub last_wait: Duration,
+}
+
+struct Bucket {
+    limit: RateLimit,
+    tokens: f64,
+    updated_at: Instant,
+    stats: RateLimitStats,
+}
+
+impl Bucket {
+    fn new(limit: RateLimit) -> Self {
+        Self {
+            limit,
+            tokens: limit.requests as f64,
+            updated_at: Instant::now(),
+            stats: RateLimitStats::default(),
+        }
+    }
+
+    /// Takes one token and returns how long the caller must wait before using it.
+    /// The balance may go negative so that queued callers are served in order.
+    fn reserve(&mut self) -> Duration {
+        let now = Instant::now();
+        let capacity = self.limit.requests as f64;
+        let rate = capacity / self.limit.per.as_secs_f64();
+
+        let elapsed = now.duration_since(self.updated_at).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * rate).min(capacity);
+        self.updated_at = now;
+        self.tokens -= 1.0;
+
+        let wait = if self.tokens >= 0.0 || !rate.is_normal() {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-self.tokens / rate)
+        };
+
+        self.stats.calls += 1;
+        if !wait.is_zero() {
+            self.stats.delayed_calls += 1;
+        }
+        self.stats.total_wait += wait;
+        self.stats.last_wait = wait;
+
+        wait
+    }
+}
+
+/// Token bucket limiter keyed by API route.
+#[derive(Default)]
+pub(crate) struct RateLimiter {
+    buckets: HashMap<String, Mutex<Bucket>>,
+}
+
+impl RateLimiter {
+    pub(crate) fn new(limits: HashMap<String, RateLimit>) -> Self {
+        Self {
+            buckets: limits
+                .into_iter()
+                .map(|(route, limit)| (route, Mutex::new(Bucket::new(limit))))
+                .collect(),
+        }
+    }
+
+    /// Waits until the route has capacity for one more request and returns the time waited.
+    pub(crate) async fn acquire(&self, route: &str) -> Duration {
+        let wait = match self.buckets.get(route) {
+            Some(bucket) => bucket.lock().expect("rate limiter lock poisoned").reserve(),
+            None => Duration::ZERO,
+        };
+        if !wait.is_zero() {
+            Delay::new(wait).await;
+        }
+        wait
+    }
+
+    pub(crate) fn stats(&self, route: &str) -> Option<RateLimitStats> {
+        self.buckets
+            .get(route)
+            .map(|bucket| bucket.lock().expect("rate limiter lock poisoned").stats)
+    }
+}
@@ -0,0 +1,85 @@
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+    time::Duration,
+};
+
+use reqwest::header;
+
+/// Controls how failed requests are retried.
+///
+/// Only failures that are safe to repeat are retried, see [`CohereApiError::is_retryable`](crate::CohereApiError::is_retryable):
+/// connection errors, `429 Too Many Requests`, `502 Bad Gateway`, `503 Service Unavailable`
+/// and `504 Gateway Timeout`.
+#[derive(Clone, Debug)]
+pub struct RetryPolicy {
+    /// Total number of attempts, including the first one. `1` disables retries.
+    pub max_attempts: u32,
+    /// Delay before the first retry.
+    pub initial_backoff: Duration,
+    /// Upper bound for the delay between two attempts.
+    pub max_backoff: Duration,
+    /// Factor applied to the delay after every attempt.
+    pub multiplier: f64,
+    /// Randomize each delay to avoid many clients retrying in lockstep.
+    pub jitter: bool,
+    /// Wait for the duration given by the `Retry-After` response header when present, up to
+    /// `max_backoff`.
+    pub respect_retry_after: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(30),
+            multiplier: 2.0,
+            jitter: true,
+            respect_retry_after: true,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// A policy that never retries.
+    pub fn none() -> Self {
+        Self {
+            max_attempts: 1,
+            ..Default::default()
+        }
+    }
+
+    /// Delay to wait after the given (1-based) failed attempt.
+    pub(crate) fn backoff(&self, attempt: u32, retry_after: Option<Duration>) -> Duration {
+        if let (true, Some(retry_after)) = (self.respect_retry_after, retry_after) {
+            return retry_after.min(self.max_backoff);
+        }
+
+        let exponent = attempt.saturating_sub(1).min(i32::MAX as u32) as i32;
+        let delay = self
+            .initial_backoff
+            .mul_f64(self.multiplier.max(1.0).powi(exponent).min(u32::MAX as f64))
+            .min(self.max_backoff);
+
+        if self.jitter {
+            // equal jitter: keep half of the delay and randomize the other half
+            let half = delay / 2;
+            half + half.mul_f64(random_fraction())
+        } else {
+            delay
+        }
+    }
+}
+
+/// Parses a `Retry-After` header expressed in seconds.
+pub(crate) fn retry_after(headers: &header::HeaderMap) -> Option<Duration> {
+    let value = headers.get(header::RETRY_AFTER)?.to_str().ok()?;
+    let seconds = value.trim().parse::<f64>().ok()?;
+    (seconds.is_finite() && seconds >= 0.0).then(|| Duration::from_secs_f64(seconds))
+}
+
+fn random_fraction() -> f64 {
+    let random = RandomState::new().build_hasher().finish();
+    (random >> 11) as f64 / (1u64 << 53) as f64
+}
@@ -0,0 +1,218 @@
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures_util::{stream::BoxStream, Stream, StreamExt};
+use serde_json::Deserializer;
+
+use crate::{
+    api::chat::{ChatCitation, ChatDocument, ChatResponse, StreamEvent, ToolCall},
+    CohereStreamError,
+};
+
+/// Events of a streamed chat, see [`Cohere::chat_stream`](crate::Cohere::chat_stream).
+///
+/// The events are read from the response body as they are polled, no background task is spawned,
+/// so the stream works with any async runtime able to drive the transport. As a [`Stream`], it
+/// composes with the combinators of `futures`, and the body is only read as fast as the events
+/// are consumed. Dropping it drops the response body, which aborts the request.
+pub struct ChatStream {
+    events: BoxStream<'static, Result<StreamEvent, CohereStreamError>>,
+}
+
+impl ChatStream {
+    pub(crate) fn new(events: BoxStream<'static, Result<StreamEvent, CohereStreamError>>) -> Self {
+        Self { events }
+    }
+
+    /// Waits for the next event, returning `None` once the stream has ended.
+    pub async fn recv(&mut self) -> Option<Result<StreamEvent, CohereStreamError>> {
+        self.events.next().await
+    }
+
+    /// Reads the whole stream, returning the response [`Cohere::chat`](crate::Cohere::chat) would have.
+    pub async fn response(mut self) -> Result<ChatResponse, CohereStreamError> {
+        let mut aggregator = ChatStreamAggregator::new();
+        while let Some(event) = self.events.next().await {
+            aggregator.push(&event?);
+        }
+        aggregator.finish()
+    }
+}
+
+impl Stream for ChatStream {
+    type Item = Result<StreamEvent, CohereStreamError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.events.poll_next_unpin(cx)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.events.size_hint()
+    }
+}
+
+impl std::fmt::Debug for ChatStream {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ChatStream").finish_non_exhaustive()
+    }
+}
+
+/// Folds the events of a streamed chat into the final [`ChatResponse`], for consumers that also
+/// handle the events as they arrive, e.g. to display the text.
+///
+/// ```no_run
+/// # async fn chat(co: cohere_rust::Cohere, request: cohere_rust::api::chat::ChatStreamRequest<'_>)
+/// # -> Result<(), Box<dyn std::error::Error>> {
+/// use cohere_rust::{api::chat::StreamEvent, ChatStreamAggregator};
+/// use futures_util::StreamExt;
+///
+/// let mut stream = co.chat_stream(&request).await?;
+/// let mut aggregator = ChatStreamAggregator::new();
+/// while let Some(event) = stream.next().await {
+///     let event = event?;
+///     aggregator.push(&event);
+///     if let StreamEvent::TextGeneration { text, .. } = event {
+///         print!("{text}");
+///     }
+/// }
+/// let response = aggregator.finish()?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Default)]
+pub struct ChatStreamAggregator {
+    text: String,
+    citations: Vec<ChatCitation>,
+    documents: Vec<ChatDocument>,
+    tool_calls: Vec<ToolCall>,
+    /// The response of the `stream-end` event, with its finish reason.
+    end: Option<(ChatResponse, String)>,
+}
+
+impl ChatStreamAggregator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds an event to the response. Events after `stream-end` are ignored.
+    pub fn push(&mut self, event: &StreamEvent) {
+        if self.end.is_some() {
+            return;
+        }
+
+        match event {
+            StreamEvent::TextGeneration { text, .. } => self.text.push_str(text),
+            StreamEvent::CitationGeneration { citations, .. } => {
+                self.citations.extend(citations.iter().cloned());
+            }
+            StreamEvent::SearchResults {
+                documents: Some(documents),
+                ..
+            } => self.documents.extend(documents.iter().cloned()),
+            StreamEvent::ToolCallsGeneration { tool_calls, .. } => {
+                self.tool_calls.extend(tool_calls.iter().cloned());
+            }
+            StreamEvent::End {
+                finish_reason,
+                response,
+                ..
+            } => self.end = Some((response.clone(), finish_reason.clone())),
+            _ => {}
+        }
+    }
+
+    /// Whether the `stream-end` event was pushed.
+    pub fn is_finished(&self) -> bool {
+        self.end.is_some()
+    }
+
+    /// The text generated so far.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// The final response: the one of the `stream-end` event, completed with what the previous
+    /// events carried. Fails when the stream ended without a `stream-end` event.
+    pub fn finish(self) -> Result<ChatResponse, CohereStreamError> {
+        let Some((mut response, finish_reason)) = self.end else {
+            return Err(CohereStreamError::Incomplete);
+        };
+
+        if response.text.is_empty() {
+            response.text = self.text;
+        }
+        response.finish_reason.get_or_insert(finish_reason);
+        if response.citations.is_none() && !self.citations.is_empty() {
+            response.citations = Some(self.citations);
+        }
+        if response.documents.is_none() && !self.documents.is_empty() {
+            response.documents = Some(self.documents);
+        }
+        if response.tool_calls.is_none() && !self.tool_calls.is_empty() {
+            response.tool_calls = Some(self.tool_calls);
+        }
+        Ok(response)
+    }
+}
+
+/// Splits the body of a streamed chat into events, whatever the way it is chunked.
+///
+/// Events are newline delimited JSON, optionally framed as server-sent events (`data: {...}`).
+/// An event split over several chunks is buffered until its line is complete, and several
+/// events in one chunk, or on one line, are all decoded.
+#[derive(Default)]
+pub(crate) struct EventDecoder {
+    buffer: Vec<u8>,
+}
+
+impl EventDecoder {
+    /// Decodes the events completed by the chunk.
+    pub(crate) fn push(&mut self, chunk: &[u8]) -> Vec<Result<StreamEvent, serde_json::Error>> {
+        self.buffer.extend_from_slice(chunk);
+        let Some(end) = self.buffer.iter().rposition(|byte| *byte == b'\n') else {
+            return Vec::new();
+        };
+
+        let lines: Vec<u8> = self.buffer.drain(..=end).collect();
+        lines
+            .split(|byte| *byte == b'\n')
+            .flat_map(decode_line)
+            .collect()
+    }
+
+    /// Decodes the events left in the buffer once the body has ended without a final newline.
+    pub(crate) fn finish(&mut self) -> Vec<Result<StreamEvent, serde_json::Error>> {
+        decode_line(&std::mem::take(&mut self.buffer))
+    }
+}
+
+fn decode_line(line: &[u8]) -> Vec<Result<StreamEvent, serde_json::Error>> {
+    let line = line.trim_ascii();
+    let payload = match line.strip_prefix(b"data:") {
+        Some(data) => data.trim_ascii(),
+        // other server-sent event fields and comments carry no event
+        None if [&b"event:"[..], b"id:", b"retry:", b":"]
+            .iter()
+            .any(|field| line.starts_with(field)) =>
+        {
+            return Vec::new();
+        }
+        None => line,
+    };
+    if payload.is_empty() || payload == b"[DONE]" {
+        return Vec::new();
+    }
+
+    let mut events = Vec::new();
+    for event in Deserializer::from_slice(payload).into_iter::<StreamEvent>() {
+        let failed = event.is_err();
+        events.push(event);
+        // the rest of the line cannot be decoded after an invalid event
+        if failed {
+            break;
+        }
+    }
+    events
+}
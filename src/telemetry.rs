@@ -0,0 +1,215 @@
+//! `tracing` instrumentation, enabled by the `tracing` feature.
+//!
+//! Each call opens a `cohere.request` span whose fields follow the OpenTelemetry GenAI semantic
+//! conventions where they apply. Without the feature every function here is a no-op.
+
+use std::{future::Future, time::Duration};
+
+use crate::{api::chat::StreamEvent, ApiWarning, CohereApiError, ResponseMeta};
+
+#[cfg(feature = "tracing")]
+pub(crate) type Span = tracing::Span;
+
+#[cfg(not(feature = "tracing"))]
+#[derive(Clone, Debug)]
+pub(crate) struct Span;
+
+/// Opens the span of a call to the given route.
+pub(crate) fn request_span(route: &str, model: Option<&str>) -> Span {
+    #[cfg(feature = "tracing")]
+    {
+        let operation = operation_name(route);
+        let name = match model {
+            Some(model) => format!("{operation} {model}"),
+            None => operation.to_string(),
+        };
+
+        tracing::info_span!(
+            target: "cohere_rust",
+            "cohere.request",
+            otel.name = %name,
+            otel.kind = "client",
+            gen_ai.system = "cohere",
+            gen_ai.operation.name = operation,
+            gen_ai.request.model = model,
+            cohere.route = route,
+            http.response.status_code = tracing::field::Empty,
+            cohere.attempts = tracing::field::Empty,
+            cohere.latency_ms = tracing::field::Empty,
+            gen_ai.response.id = tracing::field::Empty,
+            gen_ai.response.finish_reasons = tracing::field::Empty,
+            gen_ai.usage.input_tokens = tracing::field::Empty,
+            gen_ai.usage.output_tokens = tracing::field::Empty,
+            error.type = tracing::field::Empty,
+        )
+    }
+    #[cfg(not(feature = "tracing"))]
+    {
+        let _ = (route, model);
+        Span
+    }
+}
+
+/// The `gen_ai.operation.name` of a route.
+#[cfg(feature = "tracing")]
+fn operation_name(route: &str) -> &str {
+    match route {
+        "generate" => "text_completion",
+        "embed" => "embeddings",
+        route => route,
+    }
+}
+
+/// Runs the future inside the span, then records its latency and outcome.
+pub(crate) async fn instrument<T, F: Future<Output = Result<T, CohereApiError>>>(
+    span: &Span,
+    future: F,
+) -> Result<T, CohereApiError> {
+    #[cfg(feature = "tracing")]
+    {
+        use tracing::Instrument;
+
+        let started = std::time::Instant::now();
+        let result = future.instrument(span.clone()).await;
+        span.record("cohere.latency_ms", started.elapsed().as_millis() as u64);
+        if let Err(error) = &result {
+            span.record("error.type", error_type(error).as_str());
+            tracing::warn!(target: "cohere_rust", parent: span, %error, "request failed");
+        }
+        result
+    }
+    #[cfg(not(feature = "tracing"))]
+    {
+        let _ = span;
+        future.await
+    }
+}
+
+/// The `error.type` of an error: the HTTP status when the API answered, its kind otherwise.
+#[cfg(feature = "tracing")]
+fn error_type(error: &CohereApiError) -> String {
+    match error.details() {
+        Some(details) => details.status.as_str().to_string(),
+        None => error.kind().to_string(),
+    }
+}
+
+/// Records the outcome of one attempt on the current span.
+pub(crate) fn record_attempt(attempt: u32, result: &Result<reqwest::StatusCode, &CohereApiError>) {
+    #[cfg(feature = "tracing")]
+    {
+        let span = tracing::Span::current();
+        span.record("cohere.attempts", attempt);
+        let status = match result {
+            Ok(status) => Some(*status),
+            Err(error) => error.details().map(|details| details.status),
+        };
+        if let Some(status) = status {
+            span.record("http.response.status_code", status.as_u16());
+        }
+    }
+    #[cfg(not(feature = "tracing"))]
+    let _ = (attempt, result);
+}
+
+/// Notes that an attempt failed and is about to be retried.
+pub(crate) fn record_retry(attempt: u32, backoff: Duration, error: &CohereApiError) {
+    #[cfg(feature = "tracing")]
+    tracing::debug!(
+        target: "cohere_rust",
+        attempt,
+        backoff_ms = backoff.as_millis() as u64,
+        %error,
+        "retrying request"
+    );
+    #[cfg(not(feature = "tracing"))]
+    let _ = (attempt, backoff, error);
+}
+
+/// Records the response id and billed tokens on the current span.
+pub(crate) fn record_meta(meta: &ResponseMeta) {
+    #[cfg(feature = "tracing")]
+    {
+        let span = tracing::Span::current();
+        if let Some(request_id) = &meta.request_id {
+            span.record("gen_ai.response.id", request_id.as_str());
+        }
+        if let Some(billed_units) = &meta.billed_units {
+            record_usage(&span, billed_units.input_tokens, billed_units.output_tokens);
+        }
+    }
+    #[cfg(not(feature = "tracing"))]
+    let _ = meta;
+}
+
+#[cfg(feature = "tracing")]
+fn record_usage(span: &Span, input_tokens: Option<f64>, output_tokens: Option<f64>) {
+    if let Some(input_tokens) = input_tokens {
+        span.record("gen_ai.usage.input_tokens", input_tokens as u64);
+    }
+    if let Some(output_tokens) = output_tokens {
+        span.record("gen_ai.usage.output_tokens", output_tokens as u64);
+    }
+}
+
+/// Emits an event for a chunk of a streamed chat, in the span of the call.
+pub(crate) fn stream_event(span: &Span, event: &Result<StreamEvent, serde_json::Error>) {
+    #[cfg(feature = "tracing")]
+    match event {
+        Ok(StreamEvent::Start { generation_id, .. }) => {
+            span.record("gen_ai.response.id", generation_id.as_str());
+            tracing::debug!(target: "cohere_rust", parent: span, event_type = "stream-start");
+        }
+        Ok(StreamEvent::TextGeneration { text, .. }) => {
+            tracing::trace!(
+                target: "cohere_rust",
+                parent: span,
+                event_type = "text-generation",
+                text_length = text.len(),
+            );
+        }
+        Ok(StreamEvent::End {
+            finish_reason,
+            response,
+            ..
+        }) => {
+            span.record("gen_ai.response.finish_reasons", finish_reason.as_str());
+            if let Some(billed_units) = response
+                .meta
+                .as_ref()
+                .and_then(|meta| meta.billed_units.as_ref())
+            {
+                record_usage(span, billed_units.input_tokens, billed_units.output_tokens);
+            }
+            tracing::debug!(
+                target: "cohere_rust",
+                parent: span,
+                event_type = "stream-end",
+                finish_reason = finish_reason.as_str(),
+            );
+        }
+        Ok(event) => {
+            tracing::debug!(target: "cohere_rust", parent: span, event_type = event.event_type());
+        }
+        Err(error) => {
+            tracing::warn!(target: "cohere_rust", parent: span, %error, "invalid stream event");
+        }
+    }
+    #[cfg(not(feature = "tracing"))]
+    let _ = (span, event);
+}
+
+/// Logs the warnings of a response when no warning handler is configured.
+pub(crate) fn warnings(warnings: &[ApiWarning]) {
+    #[cfg(feature = "tracing")]
+    for warning in warnings {
+        tracing::warn!(
+            target: "cohere_rust",
+            deprecation = warning.is_deprecation(),
+            "{}",
+            warning.message
+        );
+    }
+    #[cfg(not(feature = "tracing"))]
+    let _ = warnings;
+}
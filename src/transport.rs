@@ -0,0 +1,116 @@
+use std::time::Duration;
+
+use bytes::Bytes;
+use futures_util::{future::BoxFuture, stream::BoxStream, StreamExt, TryStreamExt};
+use reqwest::{header, Method, StatusCode, Url};
+
+use crate::CohereApiError;
+
+/// Streamed body of an [`HttpResponse`].
+pub type ResponseBody = BoxStream<'static, Result<Bytes, CohereApiError>>;
+
+/// Sends the HTTP requests made by the client.
+///
+/// [`ReqwestTransport`] is used by default. Implement this trait to route calls through
+/// another HTTP stack, or to answer them in memory in tests.
+pub trait Transport: Send + Sync {
+    fn send(&self, request: HttpRequest) -> BoxFuture<'_, Result<HttpResponse, CohereApiError>>;
+}
+
+impl<T: Transport + ?Sized> Transport for std::sync::Arc<T> {
+    fn send(&self, request: HttpRequest) -> BoxFuture<'_, Result<HttpResponse, CohereApiError>> {
+        (**self).send(request)
+    }
+}
+
+/// An HTTP request ready to be sent.
+#[derive(Debug, Clone)]
+pub struct HttpRequest {
+    pub method: Method,
+    pub url: Url,
+    /// All the headers of the request, including the authorization header.
+    pub headers: header::HeaderMap,
+    /// The JSON encoded payload.
+    pub body: Bytes,
+    /// Timeout overriding the default one of the transport.
+    pub timeout: Option<Duration>,
+}
+
+/// An HTTP response, whose body is read as a stream of chunks.
+pub struct HttpResponse {
+    pub status: StatusCode,
+    pub headers: header::HeaderMap,
+    pub body: ResponseBody,
+}
+
+impl HttpResponse {
+    /// A response whose body is sent as a single chunk.
+    pub fn new<B: Into<Bytes>>(status: StatusCode, headers: header::HeaderMap, body: B) -> Self {
+        Self::from_chunks(status, headers, vec![body.into()])
+    }
+
+    /// A response whose body is sent as the given chunks, in order.
+    pub fn from_chunks(status: StatusCode, headers: header::HeaderMap, chunks: Vec<Bytes>) -> Self {
+        Self {
+            status,
+            headers,
+            body: futures_util::stream::iter(chunks.into_iter().map(Ok)).boxed(),
+        }
+    }
+
+    /// Reads the whole body.
+    pub async fn bytes(self) -> Result<Bytes, CohereApiError> {
+        let chunks: Vec<Bytes> = self.body.try_collect().await?;
+        Ok(match chunks.len() {
+            1 => chunks.into_iter().next().unwrap_or_default(),
+            _ => chunks.concat().into(),
+        })
+    }
+}
+
+impl std::fmt::Debug for HttpResponse {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HttpResponse")
+            .field("status", &self.status)
+            .field("headers", &self.headers)
+            .finish_non_exhaustive()
+    }
+}
+
+/// The default [`Transport`], backed by a [`reqwest::Client`].
+#[derive(Clone, Debug)]
+pub struct ReqwestTransport {
+    client: reqwest::Client,
+}
+
+impl ReqwestTransport {
+    pub fn new(client: reqwest::Client) -> Self {
+        Self { client }
+    }
+}
+
+impl Transport for ReqwestTransport {
+    fn send(&self, request: HttpRequest) -> BoxFuture<'_, Result<HttpResponse, CohereApiError>> {
+        Box::pin(async move {
+            let mut builder = self
+                .client
+                .request(request.method, request.url)
+                .headers(request.headers)
+                .body(request.body);
+            if let Some(timeout) = request.timeout {
+                builder = builder.timeout(timeout);
+            }
+
+            let response = builder.send().await?;
+
+            Ok(HttpResponse {
+                status: response.status(),
+                headers: response.headers().clone(),
+                body: response
+                    .bytes_stream()
+                    .map_err(CohereApiError::from)
+                    .boxed(),
+            })
+        })
+    }
+}
@@ -0,0 +1,106 @@
+use std::fmt;
+
+/// Client-side checks of the limits documented by the API, run before a request is sent.
+///
+/// A request breaking them fails with [`CohereApiError::Validation`](crate::CohereApiError::Validation)
+/// without reaching the network.
+pub trait Validate {
+    /// Returns every field breaking a documented limit.
+    fn validate(&self) -> Result<(), Vec<FieldError>>;
+}
+
+impl<T: Validate + ?Sized> Validate for &T {
+    fn validate(&self) -> Result<(), Vec<FieldError>> {
+        (**self).validate()
+    }
+}
+
+/// Requests without a body.
+impl Validate for () {
+    fn validate(&self) -> Result<(), Vec<FieldError>> {
+        Ok(())
+    }
+}
+
+/// A field of a request breaking a documented limit.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FieldError {
+    /// The name of the field, e.g. `"num_generations"`.
+    pub field: String,
+    /// What the field should be, e.g. `"must be between 1 and 5"`.
+    pub message: String,
+}
+
+impl fmt::Display for FieldError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "`{}` {}", self.field, self.message)
+    }
+}
+
+/// Joins the errors of a failed validation, for display.
+pub(crate) fn describe(errors: &[FieldError]) -> String {
+    errors
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Collects the errors of the fields of a request.
+#[derive(Default)]
+pub(crate) struct Validator {
+    errors: Vec<FieldError>,
+}
+
+impl Validator {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    fn error<F: Into<String>>(&mut self, field: F, message: String) {
+        self.errors.push(FieldError {
+            field: field.into(),
+            message,
+        });
+    }
+
+    /// Checks that the value, when set, is within `min..=max`.
+    pub(crate) fn range<F: Into<String>>(
+        &mut self,
+        field: F,
+        value: Option<f64>,
+        min: f64,
+        max: f64,
+    ) -> &mut Self {
+        if value.is_some_and(|value| !(min..=max).contains(&value)) {
+            self.error(field, format!("must be between {min} and {max}"));
+        }
+        self
+    }
+
+    /// Checks that the value, when set, is not negative.
+    pub(crate) fn non_negative<F: Into<String>>(
+        &mut self,
+        field: F,
+        value: Option<f64>,
+    ) -> &mut Self {
+        if value.is_some_and(|value| value.is_nan() || value < 0.0) {
+            self.error(field, "must not be negative".to_string());
+        }
+        self
+    }
+
+    pub(crate) fn not_empty<F: Into<String>, T>(&mut self, field: F, values: &[T]) -> &mut Self {
+        if values.is_empty() {
+            self.error(field, "must not be empty".to_string());
+        }
+        self
+    }
+
+    pub(crate) fn finish(&mut self) -> Result<(), Vec<FieldError>> {
+        match self.errors.is_empty() {
+            true => Ok(()),
+            false => Err(std::mem::take(&mut self.errors)),
+        }
+    }
+}
@@ -0,0 +1,111 @@
+use std::sync::Arc;
+
+use reqwest::header;
+
+const API_WARNING_HEADER: &str = "X-API-Warning";
+const DEPRECATION_HEADER: &str = "Deprecation";
+const SUNSET_HEADER: &str = "Sunset";
+
+/// Callback invoked for every warning returned by the API or raised by the client.
+pub type WarningHandler = Arc<dyn Fn(&ApiWarning) + Send + Sync>;
+
+/// A warning returned by the API alongside a successful response, or raised by the client.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ApiWarning {
+    /// The warning as sent by the API, or as described by the client.
+    pub message: String,
+    pub kind: WarningKind,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum WarningKind {
+    /// The model or endpoint used by the request is deprecated and will be retired.
+    Deprecation {
+        /// The deprecated model, when the warning names one.
+        model: Option<String>,
+        /// When the deprecated feature will stop working, as given by the `Sunset` header.
+        sunset: Option<String>,
+    },
+    /// The embeddings of the call could not be written to the
+    /// [`EmbeddingStore`](crate::EmbeddingStore). They are returned all the same.
+    Storage,
+    /// Any other warning.
+    Other,
+}
+
+impl ApiWarning {
+    pub fn is_deprecation(&self) -> bool {
+        matches!(self.kind, WarningKind::Deprecation { .. })
+    }
+
+    fn from_message(message: String, sunset: Option<String>) -> Self {
+        let kind = if message.to_lowercase().contains("deprecat") {
+            WarningKind::Deprecation {
+                model: quoted(&message),
+                sunset,
+            }
+        } else {
+            WarningKind::Other
+        };
+        ApiWarning { message, kind }
+    }
+}
+
+/// Collects the warnings carried by the response headers.
+pub(crate) fn warnings(headers: &header::HeaderMap) -> Vec<ApiWarning> {
+    let sunset = header_str(headers, SUNSET_HEADER);
+
+    let mut warnings: Vec<ApiWarning> = headers
+        .get_all(API_WARNING_HEADER)
+        .iter()
+        .map(|value| {
+            let message = String::from_utf8_lossy(value.as_bytes()).into_owned();
+            ApiWarning::from_message(message, sunset.clone())
+        })
+        .collect();
+
+    if let Some(deprecation) = header_str(headers, DEPRECATION_HEADER) {
+        if !warnings.iter().any(ApiWarning::is_deprecation) {
+            warnings.push(ApiWarning {
+                message: format!("{DEPRECATION_HEADER}: {deprecation}"),
+                kind: WarningKind::Deprecation {
+                    model: None,
+                    sunset,
+                },
+            });
+        }
+    }
+
+    warnings
+}
+
+/// Adds the warnings listed in the `meta` of a response body, skipping those already sent as headers.
+pub(crate) fn extend_with_body_warnings(
+    warnings: &mut Vec<ApiWarning>,
+    messages: Vec<String>,
+    headers: &header::HeaderMap,
+) {
+    for message in messages {
+        if !warnings.iter().any(|warning| warning.message == message) {
+            warnings.push(ApiWarning::from_message(
+                message,
+                header_str(headers, SUNSET_HEADER),
+            ));
+        }
+    }
+}
+
+fn header_str(headers: &header::HeaderMap, name: &str) -> Option<String> {
+    headers
+        .get(name)
+        .map(|value| String::from_utf8_lossy(value.as_bytes()).into_owned())
+}
+
+/// Returns the first value wrapped in quotes or backticks, which is how models are named in warnings.
+fn quoted(message: &str) -> Option<String> {
+    let start = message.find(['\'', '"', '`'])?;
+    let quote = message[start..].chars().next()?;
+    let rest = &message[start + 1..];
+    let end = rest.find(quote)?;
+    (end > 0).then(|| rest[..end].to_string())
+}
@@ -1,26 +1,44 @@
 #[cfg(test)]
 mod tests {
-    use std::collections::HashMap;
+    use std::{
+        collections::HashMap,
+        sync::{Arc, Mutex},
+        time::{Duration, Instant},
+    };
 
+    use bytes::Bytes;
     use cohere_rust::{
         api::{
-            chat::{ChatRequest, ChatResponse, ChatStreamRequest, StreamEvent},
+            chat::{
+                ChatCitation, ChatDocument, ChatRequest, ChatResponse, ChatStreamRequest,
+                CitationQuality, StreamEvent,
+            },
             classify::{Classification, ClassifyExample, ClassifyRequest, LabelProperties},
             detect_language::{DetectLanguageRequest, DetectLanguageResult},
             detokenize::DetokenizeRequest,
-            embed::EmbedRequest,
+            embed::{EmbedInputType, EmbedRequest},
             generate::{GenerateRequest, ReturnLikelihoods},
             rerank::{RerankModel, RerankRequest, RerankResult},
             summarize::{
                 SummarizeExtractiveness, SummarizeFormat, SummarizeLength, SummarizeRequest,
             },
             tokenize::TokenizeRequest,
-            GenerateModel, Truncate,
+            EmbedModel, GenerateModel, Truncate,
         },
-        Cohere,
+        ApiWarning, BilledUnits, CacheConfig, CacheStats, CancellationToken, ChatStreamAggregator,
+        Cohere, CohereApiError, CohereConfigError, CohereStreamError, EmbeddingStore,
+        FailoverPolicy, HttpRequest, HttpResponse, InMemoryMetrics, Middleware, MiddlewareRequest,
+        MiddlewareResponse, RateLimit, RequestOptions, RetryPolicy, RoundRobinCredentials,
+        Transport, Validate, WarningKind,
+    };
+    use futures_util::{future::BoxFuture, stream, StreamExt, TryStreamExt};
+    use reqwest::{
+        header::{HeaderMap, HeaderName, HeaderValue},
+        StatusCode,
     };
 
     #[tokio::test]
+    #[allow(clippy::useless_vec)]
     async fn test_classify() {
         // Create mock server
         let mut mock_server = mockito::Server::new_async().await;
@@ -338,6 +356,7 @@ mod tests {
             model: None,
             texts: &["hi".to_string()],
             truncate: Truncate::End,
+            input_type: None,
         };
 
         let response = client.embed(&request).await;
@@ -486,6 +505,7 @@ mod tests {
                     generation_id: "0c9cb118-f841-4588-b835-f9a4fe2c572e".to_string(),
                     response_id: "feab94ed-789b-42f2-8f4f-c49d56d28734".to_string(),
                     text: "Thomas P. Frank.".to_string(),
+                    ..Default::default()
                 },
             },
         ];
@@ -739,4 +759,1816 @@ mod tests {
 
         assert_eq!("API request failed with status code `500 Internal Server Error` and error message `invalid request: inputs cannot be empty`", response.to_string());
     }
+
+    #[tokio::test]
+    async fn test_builder_headers() {
+        // Create mock server
+        let mut mock_server = mockito::Server::new_async().await;
+        let mock_url = mock_server.url();
+
+        // Create a mock
+        let mock_endpoint = mock_server
+            .mock("POST", "/detokenize")
+            .match_header("authorization", "Bearer test-key")
+            .match_header("request-source", "my-service")
+            .match_header("x-tenant", "acme")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"text":"detokenized! :D"}"#)
+            .create_async()
+            .await;
+
+        let client = Cohere::builder()
+            .api_url(mock_url)
+            .api_key("test-key")
+            .request_source("my-service")
+            .default_header(
+                HeaderName::from_static("x-tenant"),
+                HeaderValue::from_static("acme"),
+            )
+            .http_client(reqwest::Client::new())
+            .build();
+
+        let response = client
+            .detokenize(&DetokenizeRequest {
+                tokens: &[10002, 1706, 1722, 5169, 4328],
+                model: None,
+            })
+            .await;
+
+        // assert that mock endpoint was called
+        mock_endpoint.assert_async().await;
+
+        assert!(response.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_retry_policy() {
+        // Create mock server
+        let mut mock_server = mockito::Server::new_async().await;
+        let mock_url = mock_server.url();
+
+        // Create mocks, the first one answers twice before the second one takes over
+        let mock_unavailable = mock_server
+            .mock("POST", "/detokenize")
+            .with_status(503)
+            .with_header("retry-after", "1")
+            .with_body(r#"{"message":"service unavailable"}"#)
+            .expect(2)
+            .create_async()
+            .await;
+        let mock_endpoint = mock_server
+            .mock("POST", "/detokenize")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"text":"detokenized! :D"}"#)
+            .create_async()
+            .await;
+
+        let client = Cohere::builder()
+            .api_url(mock_url)
+            .api_key("test-key")
+            .retry_policy(RetryPolicy {
+                max_attempts: 3,
+                initial_backoff: Duration::from_millis(1),
+                jitter: false,
+                ..Default::default()
+            })
+            .build();
+
+        let started = Instant::now();
+        let response = client
+            .detokenize(&DetokenizeRequest {
+                tokens: &[10002, 1706, 1722, 5169, 4328],
+                model: None,
+            })
+            .await;
+
+        // assert that both mock endpoints were called
+        mock_unavailable.assert_async().await;
+        mock_endpoint.assert_async().await;
+
+        // each retry waited for the `Retry-After` of the 503 rather than the 1ms backoff
+        assert!(started.elapsed() >= Duration::from_secs(2));
+        assert!(response.is_ok());
+        assert_eq!("detokenized! :D".to_string(), response.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_retry_after_cap() {
+        // Create mock server
+        let mut mock_server = mockito::Server::new_async().await;
+        let mock_url = mock_server.url();
+
+        // Create mocks, the first one answers once before the second one takes over
+        let mock_rate_limited = mock_server
+            .mock("POST", "/detokenize")
+            .with_status(429)
+            .with_header("retry-after", "3600")
+            .with_body(r#"{"message":"too many requests"}"#)
+            .expect(1)
+            .create_async()
+            .await;
+        let mock_endpoint = mock_server
+            .mock("POST", "/detokenize")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"text":"detokenized! :D"}"#)
+            .create_async()
+            .await;
+
+        let client = Cohere::builder()
+            .api_url(mock_url)
+            .api_key("test-key")
+            .retry_policy(RetryPolicy {
+                max_attempts: 2,
+                max_backoff: Duration::from_millis(10),
+                ..Default::default()
+            })
+            .build();
+
+        // the hour asked by the server is capped to `max_backoff`
+        let started = Instant::now();
+        let response = client
+            .detokenize(&DetokenizeRequest {
+                tokens: &[10002, 1706, 1722, 5169, 4328],
+                model: None,
+            })
+            .await;
+
+        assert!(started.elapsed() < Duration::from_secs(1));
+        assert_eq!("detokenized! :D".to_string(), response.unwrap());
+
+        // assert that both mock endpoints were called
+        mock_rate_limited.assert_async().await;
+        mock_endpoint.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit() {
+        // Create mock server
+        let mut mock_server = mockito::Server::new_async().await;
+        let mock_url = mock_server.url();
+
+        // Create a mock
+        let mock_endpoint = mock_server
+            .mock("POST", "/detokenize")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"text":"detokenized! :D"}"#)
+            .expect(3)
+            .create_async()
+            .await;
+
+        let client = Cohere::builder()
+            .api_url(mock_url)
+            .api_key("test-key")
+            .rate_limit(
+                "detokenize",
+                RateLimit {
+                    requests: 1,
+                    per: Duration::from_millis(50),
+                },
+            )
+            .build();
+
+        let request = DetokenizeRequest {
+            tokens: &[10002, 1706, 1722, 5169, 4328],
+            model: None,
+        };
+        for _ in 0..3 {
+            assert!(client.detokenize(&request).await.is_ok());
+        }
+
+        // assert that mock endpoint was called
+        mock_endpoint.assert_async().await;
+
+        let stats = client.rate_limit_stats("detokenize").unwrap();
+        assert_eq!(3, stats.calls);
+        assert_eq!(2, stats.delayed_calls);
+        assert!(stats.total_wait > Duration::ZERO);
+        assert!(client.rate_limit_stats("embed").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_api_rate_limited() {
+        // Create mock server
+        let mut mock_server = mockito::Server::new_async().await;
+        let mock_url = mock_server.url();
+
+        // Create a mock
+        let mock_endpoint = mock_server
+            .mock("POST", "/tokenize")
+            .with_status(429)
+            .with_header("content-type", "application/json")
+            .with_header("retry-after", "7")
+            .with_header("x-request-id", "f0a1b2c3")
+            .with_body(r#"{"message":"too many requests"}"#)
+            .create_async()
+            .await;
+
+        let client = Cohere::new(mock_url, "test-key");
+
+        let request = TokenizeRequest {
+            text: "tokenize me! :D",
+            model: None,
+        };
+
+        let response = client.tokenize(&request).await;
+
+        // assert that mock endpoint was called
+        mock_endpoint.assert_async().await;
+
+        let error = response.err().unwrap();
+
+        assert!(error.is_retryable());
+        assert_eq!(Some(Duration::from_secs(7)), error.retry_after());
+        match error {
+            CohereApiError::RateLimited { details, .. } => {
+                assert_eq!(StatusCode::TOO_MANY_REQUESTS, details.status);
+                assert_eq!("too many requests", details.message);
+                assert_eq!(r#"{"message":"too many requests"}"#, details.body);
+                assert_eq!(Some("f0a1b2c3".to_string()), details.request_id);
+            }
+            error => panic!("unexpected error: {error:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_api_warning() {
+        // Create mock server
+        let mut mock_server = mockito::Server::new_async().await;
+        let mock_url = mock_server.url();
+
+        // Create a mock
+        let mock_endpoint = mock_server
+            .mock("POST", "/detokenize")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_header(
+                "x-api-warning",
+                "The model 'command-light-nightly' is deprecated and will be removed",
+            )
+            .with_header("sunset", "Wed, 11 Nov 2026 23:59:59 GMT")
+            .with_body(r#"{"text":"detokenized! :D"}"#)
+            .create_async()
+            .await;
+
+        let warnings = Arc::new(Mutex::new(Vec::new()));
+        let handler_warnings = warnings.clone();
+
+        let client = Cohere::builder()
+            .api_url(mock_url)
+            .api_key("test-key")
+            .on_warning(move |warning| handler_warnings.lock().unwrap().push(warning.clone()))
+            .build();
+
+        let response = client
+            .detokenize(&DetokenizeRequest {
+                tokens: &[10002, 1706, 1722, 5169, 4328],
+                model: Some(GenerateModel::CommandLightNightly),
+            })
+            .await;
+
+        // assert that mock endpoint was called
+        mock_endpoint.assert_async().await;
+
+        assert!(response.is_ok());
+
+        assert_eq!(
+            vec![ApiWarning {
+                message: "The model 'command-light-nightly' is deprecated and will be removed"
+                    .to_string(),
+                kind: WarningKind::Deprecation {
+                    model: Some("command-light-nightly".to_string()),
+                    sunset: Some("Wed, 11 Nov 2026 23:59:59 GMT".to_string()),
+                },
+            }],
+            *warnings.lock().unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_response_meta() {
+        // Create mock server
+        let mut mock_server = mockito::Server::new_async().await;
+        let mock_url = mock_server.url();
+
+        // Create a mock
+        let mock_endpoint = mock_server
+            .mock("POST", "/rerank")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_header("x-trace", "abc")
+            .with_body(
+                r#"{
+                    "id": "c8ec1a0c-27a1-4e0a-9b5c-3f7d3fd2b5a1",
+                    "results": [{ "index": 0, "relevance_score": 0.98 }],
+                    "meta": {
+                        "api_version": { "version": "1" },
+                        "billed_units": { "search_units": 1 },
+                        "warnings": ["the `rerank-english-v2.0` model is deprecated"]
+                    }
+                }"#,
+            )
+            .create_async()
+            .await;
+
+        let client = Cohere::new(mock_url, "test-key");
+
+        let response = client
+            .rerank_with_meta(&RerankRequest {
+                query: "What is the capital of the United States?",
+                documents: &["Washington, D.C. is the capital of the United States.".to_string()],
+                ..Default::default()
+            })
+            .await;
+
+        // assert that mock endpoint was called
+        mock_endpoint.assert_async().await;
+
+        let response = response.unwrap();
+
+        assert_eq!(
+            vec![RerankResult {
+                index: 0,
+                relevance_score: 0.98
+            }],
+            response.data
+        );
+        assert_eq!(
+            Some("c8ec1a0c-27a1-4e0a-9b5c-3f7d3fd2b5a1".to_string()),
+            response.meta.request_id
+        );
+        assert_eq!("1", response.meta.api_version.unwrap().version);
+        assert_eq!(
+            Some(BilledUnits {
+                search_units: Some(1.0),
+                ..Default::default()
+            }),
+            response.meta.billed_units
+        );
+        assert_eq!("abc", response.meta.headers["x-trace"]);
+        assert_eq!(1, response.meta.warnings.len());
+        assert!(response.meta.warnings[0].is_deprecation());
+        assert_eq!(1, response.meta.attempts);
+    }
+
+    #[test]
+    fn test_invalid_config() {
+        assert!(matches!(
+            Cohere::try_new("not a url", "test-key"),
+            Err(CohereConfigError::InvalidUrl { .. })
+        ));
+        assert!(matches!(
+            Cohere::try_new("mailto:api@cohere.ai", "test-key"),
+            Err(CohereConfigError::InvalidUrl { .. })
+        ));
+        assert!(matches!(
+            Cohere::try_new("https://api.cohere.ai/v1", "test\nkey"),
+            Err(CohereConfigError::InvalidApiKey(_))
+        ));
+        assert!(matches!(
+            Cohere::builder()
+                .api_key("test-key")
+                .rate_limit("embed", RateLimit::per_second(0))
+                .try_build(),
+            Err(CohereConfigError::InvalidRateLimit { .. })
+        ));
+        assert!(matches!(
+            Cohere::builder()
+                .api_key("test-key")
+                .rate_limit(
+                    "embed",
+                    RateLimit {
+                        requests: 10,
+                        per: Duration::ZERO,
+                    },
+                )
+                .try_build(),
+            Err(CohereConfigError::InvalidRateLimit { .. })
+        ));
+        assert!(Cohere::try_new("https://api.cohere.ai/v1", "test-key").is_ok());
+    }
+
+    struct FakeTransport {
+        requests: Mutex<Vec<HttpRequest>>,
+    }
+
+    impl Transport for FakeTransport {
+        fn send(
+            &self,
+            request: HttpRequest,
+        ) -> BoxFuture<'_, Result<HttpResponse, CohereApiError>> {
+            self.requests.lock().unwrap().push(request);
+            Box::pin(async {
+                Ok(HttpResponse::new(
+                    StatusCode::OK,
+                    HeaderMap::new(),
+                    r#"{"text":"detokenized! :D"}"#,
+                ))
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_custom_transport() {
+        let transport = Arc::new(FakeTransport {
+            requests: Mutex::new(Vec::new()),
+        });
+
+        let client = Cohere::builder()
+            .api_url("https://api.cohere.ai/v1")
+            .api_key("test-key")
+            .transport(transport.clone())
+            .build();
+
+        let response = client
+            .detokenize(&DetokenizeRequest {
+                tokens: &[10002, 1706],
+                model: None,
+            })
+            .await;
+
+        assert_eq!("detokenized! :D".to_string(), response.unwrap());
+
+        let requests = transport.requests.lock().unwrap();
+        assert_eq!(1, requests.len());
+        assert_eq!(
+            "https://api.cohere.ai/v1/detokenize",
+            requests[0].url.as_str()
+        );
+        assert_eq!("Bearer test-key", requests[0].headers["authorization"]);
+        assert_eq!(
+            r#"{"tokens":[10002,1706]}"#.as_bytes(),
+            &requests[0].body[..]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_request_options() {
+        // Create mock server
+        let mut mock_server = mockito::Server::new_async().await;
+        let mock_url = mock_server.url();
+
+        // Create a mock
+        let mock_endpoint = mock_server
+            .mock("POST", "/detokenize")
+            .match_header("authorization", "Bearer other-key")
+            .match_header("idempotency-key", "detokenize-1")
+            .match_header("x-tenant", "acme")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"text":"detokenized! :D"}"#)
+            .expect(1)
+            .create_async()
+            .await;
+
+        let client = Cohere::new(mock_url, "test-key");
+        let request = DetokenizeRequest {
+            tokens: &[10002, 1706, 1722, 5169, 4328],
+            model: None,
+        };
+
+        let response = client
+            .with_options(
+                RequestOptions::new()
+                    .api_key("other-key")
+                    .idempotency_key("detokenize-1")
+                    .header(
+                        HeaderName::from_static("x-tenant"),
+                        HeaderValue::from_static("acme"),
+                    )
+                    .timeout(Duration::from_secs(2)),
+            )
+            .detokenize(&request)
+            .await;
+
+        assert_eq!("detokenized! :D".to_string(), response.unwrap());
+
+        let token = CancellationToken::new();
+        token.cancel();
+        let response = client
+            .with_options(RequestOptions::new().cancellation(token))
+            .detokenize(&request)
+            .await;
+
+        assert!(matches!(response, Err(CohereApiError::Cancelled)));
+
+        // assert that mock endpoint was called once, by the first request only
+        mock_endpoint.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_request_timeout() {
+        // Create mock server
+        let mut mock_server = mockito::Server::new_async().await;
+        let mock_url = mock_server.url();
+
+        // Create a mock
+        let mock_endpoint = mock_server
+            .mock("POST", "/detokenize")
+            .with_status(503)
+            .with_header("retry-after", "1")
+            .with_body(r#"{"message":"service unavailable"}"#)
+            .expect(1)
+            .create_async()
+            .await;
+
+        let client = Cohere::builder()
+            .api_url(mock_url)
+            .api_key("test-key")
+            .retry_policy(RetryPolicy {
+                max_attempts: 3,
+                ..Default::default()
+            })
+            .build();
+
+        // the timeout covers the retries, not only each attempt
+        let started = Instant::now();
+        let response = client
+            .with_options(RequestOptions::new().timeout(Duration::from_millis(200)))
+            .detokenize(&DetokenizeRequest {
+                tokens: &[10002, 1706, 1722, 5169, 4328],
+                model: None,
+            })
+            .await;
+
+        assert!(matches!(response, Err(CohereApiError::Timeout)));
+        assert!(started.elapsed() < Duration::from_secs(1));
+
+        // assert that mock endpoint was called once, before the first retry
+        mock_endpoint.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_chat_stream_timeout() {
+        // Create mock server
+        let mut mock_server = mockito::Server::new_async().await;
+        let mock_url = mock_server.url();
+
+        // Create a mock, whose events come slower than the timeout
+        let mock_endpoint = mock_server
+            .mock("POST", "/chat")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_chunked_body(|w| {
+                w.write_all(b"{\"is_finished\":false,\"event_type\":\"stream-start\",\"generation_id\":\"0c9cb118-f841-4588-b835-f9a4fe2c572e\"}\n")?;
+                w.flush()?;
+                std::thread::sleep(Duration::from_millis(600));
+                w.write_all(b"{\"is_finished\":true,\"event_type\":\"stream-end\",\"response\":{\"response_id\":\"feab94ed-789b-42f2-8f4f-c49d56d28734\",\"text\":\"Thomas\",\"generation_id\":\"0c9cb118-f841-4588-b835-f9a4fe2c572e\"},\"finish_reason\":\"COMPLETE\"}\n")
+            })
+            .create_async()
+            .await;
+
+        let client = Cohere::new(mock_url, "test-key");
+
+        // the timeout bounds the wait for the response, not the reading of the events
+        let response = client
+            .with_options(RequestOptions::new().timeout(Duration::from_millis(200)))
+            .chat_stream(&ChatStreamRequest::from(ChatRequest {
+                message: "who wrote the book where is my cheese?",
+                ..Default::default()
+            }))
+            .await
+            .unwrap()
+            .response()
+            .await;
+
+        // assert that mock endpoint was called
+        mock_endpoint.assert_async().await;
+
+        assert_eq!("Thomas", response.unwrap().text);
+    }
+
+    struct RecordingMiddleware {
+        name: &'static str,
+        events: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl Middleware for RecordingMiddleware {
+        fn on_request(&self, request: &mut MiddlewareRequest<'_>) -> Result<(), CohereApiError> {
+            request
+                .headers
+                .insert("x-middleware", HeaderValue::from_static("seen"));
+            request.body["model"] = "rewritten-model".into();
+            self.events.lock().unwrap().push(format!(
+                "{} request {} {}",
+                self.name, request.route, request.attempt
+            ));
+            Ok(())
+        }
+
+        fn on_response(&self, response: &MiddlewareResponse<'_>) {
+            self.events.lock().unwrap().push(format!(
+                "{} response {} {}",
+                self.name, response.route, response.status
+            ));
+        }
+
+        fn on_error(&self, route: &str, attempt: u32, _error: &CohereApiError) {
+            self.events
+                .lock()
+                .unwrap()
+                .push(format!("{} error {} {}", self.name, route, attempt));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_middleware() {
+        // Create mock server
+        let mut mock_server = mockito::Server::new_async().await;
+        let mock_url = mock_server.url();
+
+        // Create a mock
+        let mock_endpoint = mock_server
+            .mock("POST", "/detokenize")
+            .match_header("x-middleware", "seen")
+            .match_body(mockito::Matcher::PartialJsonString(
+                r#"{"model":"rewritten-model"}"#.to_string(),
+            ))
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"text":"detokenized! :D"}"#)
+            .expect(1)
+            .create_async()
+            .await;
+
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let client = Cohere::builder()
+            .api_url(mock_url)
+            .api_key("test-key")
+            .middleware(RecordingMiddleware {
+                name: "outer",
+                events: events.clone(),
+            })
+            .middleware(RecordingMiddleware {
+                name: "inner",
+                events: events.clone(),
+            })
+            .build();
+
+        let request = DetokenizeRequest {
+            tokens: &[10002, 1706, 1722, 5169, 4328],
+            model: None,
+        };
+
+        let response = client.detokenize(&request).await;
+
+        assert_eq!("detokenized! :D".to_string(), response.unwrap());
+        assert_eq!(
+            vec![
+                "outer request detokenize 1",
+                "inner request detokenize 1",
+                "inner response detokenize 200 OK",
+                "outer response detokenize 200 OK",
+            ],
+            *events.lock().unwrap()
+        );
+
+        // assert that mock endpoint was called once
+        mock_endpoint.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_metrics() {
+        // Create mock server
+        let mut mock_server = mockito::Server::new_async().await;
+        let mock_url = mock_server.url();
+
+        // Create mocks
+        let mock_embed = mock_server
+            .mock("POST", "/embed")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"embeddings":[[0.5,-1.25]],"meta":{"billed_units":{"input_tokens":3}}}"#)
+            .expect(2)
+            .create_async()
+            .await;
+        let mock_chat = mock_server
+            .mock("POST", "/chat")
+            .with_status(400)
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"message":"invalid request: message must not be empty"}"#)
+            .create_async()
+            .await;
+
+        let metrics = Arc::new(InMemoryMetrics::new());
+        let client = Cohere::builder()
+            .api_url(mock_url)
+            .api_key("test-key")
+            .metrics(metrics.clone())
+            .build();
+
+        let texts = ["hi".to_string()];
+        for _ in 0..2 {
+            let response = client
+                .embed(&EmbedRequest {
+                    model: Some(EmbedModel::EnglishV3),
+                    texts: &texts,
+                    truncate: Truncate::End,
+                    input_type: None,
+                })
+                .await;
+            assert!(response.is_ok());
+        }
+        let response = client.chat(&ChatRequest::default()).await;
+        assert!(matches!(response, Err(CohereApiError::BadRequest(_))));
+
+        // assert that mock endpoints were called
+        mock_embed.assert_async().await;
+        mock_chat.assert_async().await;
+
+        let snapshot = metrics.snapshot();
+        assert_eq!(2, snapshot.len());
+
+        let chat = &snapshot[0];
+        assert_eq!("chat", chat.route);
+        assert_eq!(1, chat.requests);
+        assert_eq!(Some(&1), chat.errors.get("bad_request"));
+
+        let embed = &snapshot[1];
+        assert_eq!("embed", embed.route);
+        assert_eq!(Some("embed-english-v3.0".to_string()), embed.model);
+        assert_eq!(2, embed.requests);
+        assert_eq!(0, embed.error_count());
+        assert_eq!(2, embed.latency_buckets.iter().sum::<u64>());
+        assert_eq!(6.0, embed.input_tokens);
+        assert_eq!(Some(embed.clone()), metrics.route("embed"));
+    }
+
+    #[cfg(feature = "prometheus")]
+    #[tokio::test]
+    async fn test_prometheus_metrics() {
+        // Create mock server
+        let mut mock_server = mockito::Server::new_async().await;
+        let mock_url = mock_server.url();
+
+        // Create a mock
+        let mock_endpoint = mock_server
+            .mock("POST", "/detokenize")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"text":"detokenized! :D","meta":{"billed_units":{"input_tokens":5}}}"#)
+            .create_async()
+            .await;
+
+        let metrics = Arc::new(cohere_rust::PrometheusMetrics::new());
+        let client = Cohere::builder()
+            .api_url(mock_url)
+            .api_key("test-key")
+            .metrics(metrics.clone())
+            .build();
+
+        let response = client
+            .detokenize(&DetokenizeRequest {
+                tokens: &[10002, 1706, 1722, 5169, 4328],
+                model: None,
+            })
+            .await;
+
+        // assert that mock endpoint was called
+        mock_endpoint.assert_async().await;
+        assert!(response.is_ok());
+
+        let output = metrics.render();
+        for line in [
+            "# TYPE cohere_requests_total counter",
+            r#"cohere_requests_total{route="detokenize",model=""} 1"#,
+            r#"cohere_request_duration_seconds_bucket{route="detokenize",model="",le="+Inf"} 1"#,
+            r#"cohere_request_duration_seconds_count{route="detokenize",model=""} 1"#,
+            r#"cohere_billed_units_total{route="detokenize",model="",unit="input_tokens"} 5"#,
+        ] {
+            assert!(
+                output.lines().any(|l| l == line),
+                "missing `{line}` in {output}"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_credential_rotation() {
+        // Create mock server
+        let mut mock_server = mockito::Server::new_async().await;
+        let mock_url = mock_server.url();
+
+        // Create mocks
+        let mock_rejected = mock_server
+            .mock("POST", "/detokenize")
+            .match_header(
+                "authorization",
+                mockito::Matcher::Regex("revoked".to_string()),
+            )
+            .with_status(401)
+            .with_header("content-type", "application/json")
+            .with_header("x-request-id", "request-1")
+            .with_body(r#"{"message":"invalid api token"}"#)
+            .expect(4)
+            .create_async()
+            .await;
+        let mock_accepted = mock_server
+            .mock("POST", "/detokenize")
+            .match_header("authorization", "Bearer current-key")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"text":"detokenized! :D"}"#)
+            .expect(1)
+            .create_async()
+            .await;
+
+        let request = DetokenizeRequest {
+            tokens: &[10002, 1706, 1722, 5169, 4328],
+            model: None,
+        };
+
+        // a rejected key is refreshed and the request retried once
+        let client = Cohere::builder()
+            .api_url(mock_url.clone())
+            .credentials(RoundRobinCredentials::new(["revoked-key", "current-key"]))
+            .build();
+        let response = client.detokenize_with_meta(&request).await.unwrap();
+        assert_eq!("detokenized! :D", response.data);
+        assert_eq!(2, response.meta.attempts);
+
+        // the key is still rejected after the refresh
+        let client = Cohere::builder()
+            .api_url(mock_url.clone())
+            .credentials(RoundRobinCredentials::new(["revoked-key", "revoked-key-2"]))
+            .build();
+        let response = client.detokenize(&request).await;
+        match response {
+            Err(CohereApiError::InvalidApiKey(Some(details))) => {
+                assert_eq!(Some("request-1".to_string()), details.request_id);
+                assert_eq!("invalid api token", details.message);
+            }
+            other => panic!("unexpected response: {other:?}"),
+        }
+
+        // a static key cannot be refreshed, and fails the same way without a retry
+        let client = Cohere::new(mock_url, "revoked-key");
+        let response = client.detokenize(&request).await;
+        assert!(matches!(
+            response,
+            Err(CohereApiError::InvalidApiKey(Some(_)))
+        ));
+
+        // assert that mock endpoints were called
+        mock_rejected.assert_async().await;
+        mock_accepted.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_failover() {
+        // Create mock servers
+        let mut primary = mockito::Server::new_async().await;
+        let mut secondary = mockito::Server::new_async().await;
+
+        // Create mocks
+        let mock_unavailable = primary
+            .mock("POST", "/detokenize")
+            .with_status(503)
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"message":"service unavailable"}"#)
+            .expect(1)
+            .create_async()
+            .await;
+        let mock_probe = primary
+            .mock("POST", "/check-api-key")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"valid":true}"#)
+            .expect(1)
+            .create_async()
+            .await;
+        let mock_fallback = secondary
+            .mock("POST", "/detokenize")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"text":"detokenized! :D"}"#)
+            .expect(2)
+            .create_async()
+            .await;
+
+        let client = Cohere::builder()
+            .api_urls([primary.url(), secondary.url()])
+            .api_key("test-key")
+            .failover_policy(FailoverPolicy {
+                failure_threshold: 1,
+                cooldown: Duration::from_secs(60),
+            })
+            .build();
+
+        let request = DetokenizeRequest {
+            tokens: &[10002, 1706, 1722, 5169, 4328],
+            model: None,
+        };
+
+        // the primary fails, the secondary answers
+        let response = client.detokenize(&request).await;
+        assert_eq!("detokenized! :D".to_string(), response.unwrap());
+
+        let endpoints = client.endpoints();
+        assert!(!endpoints[0].available);
+        assert_eq!(1, endpoints[0].consecutive_failures);
+        assert!(endpoints[1].available);
+
+        // the primary is skipped while its circuit is open
+        let response = client.detokenize(&request).await;
+        assert_eq!("detokenized! :D".to_string(), response.unwrap());
+
+        // a successful probe puts it back in rotation
+        let endpoints = client.probe_endpoints().await;
+        assert!(endpoints[0].available);
+        assert_eq!(0, endpoints[0].consecutive_failures);
+
+        // assert that mock endpoints were called
+        mock_unavailable.assert_async().await;
+        mock_probe.assert_async().await;
+        mock_fallback.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_failover_unreachable() {
+        // Create mock server
+        let mut secondary = mockito::Server::new_async().await;
+
+        // Create a mock
+        let mock_fallback = secondary
+            .mock("POST", "/detokenize")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"text":"detokenized! :D"}"#)
+            .expect(1)
+            .create_async()
+            .await;
+
+        // the primary never completes a connection: its accept queue is kept full, so that the
+        // handshakes of new connections are dropped as for a blackholed host
+        let primary = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let primary_url = format!("http://{}/v1", primary.local_addr().unwrap());
+        let mut queued = Vec::new();
+        while let Ok(stream) = std::net::TcpStream::connect_timeout(
+            &primary.local_addr().unwrap(),
+            Duration::from_millis(100),
+        ) {
+            queued.push(stream);
+        }
+
+        let client = Cohere::builder()
+            .api_urls([primary_url, secondary.url()])
+            .api_key("test-key")
+            .connect_timeout(Duration::from_millis(200))
+            .failover_policy(FailoverPolicy {
+                failure_threshold: 1,
+                cooldown: Duration::from_secs(60),
+            })
+            .build();
+
+        let response = client
+            .detokenize(&DetokenizeRequest {
+                tokens: &[10002, 1706, 1722, 5169, 4328],
+                model: None,
+            })
+            .await;
+        assert_eq!("detokenized! :D".to_string(), response.unwrap());
+
+        let endpoints = client.endpoints();
+        assert!(!endpoints[0].available);
+        assert_eq!(1, endpoints[0].consecutive_failures);
+
+        // assert that mock endpoint was called
+        mock_fallback.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_probe_loop() {
+        // Create mock servers
+        let mut primary = mockito::Server::new_async().await;
+        let mut secondary = mockito::Server::new_async().await;
+
+        // Create mocks
+        let mock_unavailable = primary
+            .mock("POST", "/detokenize")
+            .with_status(503)
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"message":"service unavailable"}"#)
+            .expect(1)
+            .create_async()
+            .await;
+        let mock_probe = primary
+            .mock("POST", "/check-api-key")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"valid":true}"#)
+            .create_async()
+            .await;
+        let mock_fallback = secondary
+            .mock("POST", "/detokenize")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"text":"detokenized! :D"}"#)
+            .expect(1)
+            .create_async()
+            .await;
+
+        let client = Cohere::builder()
+            .api_urls([primary.url(), secondary.url()])
+            .api_key("test-key")
+            .failover_policy(FailoverPolicy {
+                failure_threshold: 1,
+                cooldown: Duration::from_secs(60),
+            })
+            .build();
+
+        let response = client
+            .detokenize(&DetokenizeRequest {
+                tokens: &[10002, 1706, 1722, 5169, 4328],
+                model: None,
+            })
+            .await;
+        assert_eq!("detokenized! :D".to_string(), response.unwrap());
+        assert!(!client.endpoints()[0].available);
+
+        // the primary is put back in rotation in the background, long before its cooldown is over
+        let probes = tokio::spawn(client.probe_loop(Duration::from_millis(10)));
+        let started = Instant::now();
+        while !client.endpoints()[0].available && started.elapsed() < Duration::from_secs(5) {
+            futures_timer::Delay::new(Duration::from_millis(10)).await;
+        }
+        probes.abort();
+        assert!(client.endpoints()[0].available);
+
+        // assert that mock endpoints were called
+        mock_unavailable.assert_async().await;
+        mock_probe.assert_async().await;
+        mock_fallback.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_validation() {
+        // Create mock server
+        let mut mock_server = mockito::Server::new_async().await;
+        let mock_url = mock_server.url();
+
+        // Create a mock
+        let mock_endpoint = mock_server
+            .mock("POST", "/generate")
+            .expect(0)
+            .create_async()
+            .await;
+
+        let client = Cohere::new(mock_url, "test-key");
+
+        let request = GenerateRequest {
+            prompt: "Once upon a time in a magical land called",
+            num_generations: Some(6),
+            p: Some(1.5),
+            logit_bias: Some(HashMap::from([(42, 11.0), (43, -2.0)])),
+            ..Default::default()
+        };
+        let response = client.generate(&request).await;
+
+        let Err(CohereApiError::Validation(errors)) = response else {
+            panic!("expected a validation error, got {response:?}");
+        };
+        let fields: Vec<&str> = errors.iter().map(|error| error.field.as_str()).collect();
+        assert_eq!(vec!["num_generations", "p", "logit_bias[42]"], fields);
+        assert_eq!(
+            "Invalid request: `num_generations` must be between 1 and 5, `p` must be between 0 and 1, `logit_bias[42]` must be between -10 and 10",
+            CohereApiError::Validation(errors).to_string()
+        );
+
+        let request = EmbedRequest {
+            model: None,
+            texts: &[],
+            truncate: Truncate::End,
+            input_type: None,
+        };
+        assert_eq!("texts", request.validate().unwrap_err()[0].field);
+
+        // assert that the request never reached the server
+        mock_endpoint.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_response_cache() {
+        // Create mock server
+        let mut mock_server = mockito::Server::new_async().await;
+        let mock_url = mock_server.url();
+
+        // Create mocks
+        let mock_detokenize = mock_server
+            .mock("POST", "/detokenize")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"text":"detokenized! :D"}"#)
+            .expect(1)
+            .create_async()
+            .await;
+        let mock_embed_batch = mock_server
+            .mock("POST", "/embed")
+            .match_body(mockito::Matcher::PartialJsonString(
+                r#"{"texts":["hello","world"]}"#.to_string(),
+            ))
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"embeddings":[[1.0,0.0],[0.0,1.0]]}"#)
+            .expect(1)
+            .create_async()
+            .await;
+        let mock_embed_missing = mock_server
+            .mock("POST", "/embed")
+            .match_body(mockito::Matcher::PartialJsonString(
+                r#"{"texts":["again"]}"#.to_string(),
+            ))
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"embeddings":[[0.5,0.5]]}"#)
+            .expect(1)
+            .create_async()
+            .await;
+
+        let client = Cohere::builder()
+            .api_url(mock_url)
+            .api_key("test-key")
+            .cache(CacheConfig::default())
+            .build();
+
+        let request = DetokenizeRequest {
+            tokens: &[10002, 1706, 1722, 5169, 4328],
+            model: None,
+        };
+        for cached in [false, true] {
+            let response = client.detokenize_with_meta(&request).await.unwrap();
+            assert_eq!("detokenized! :D", response.data);
+            assert_eq!(cached, response.meta.cached);
+        }
+
+        let texts = ["hello".to_string(), "world".to_string()];
+        let response = client
+            .embed(&EmbedRequest {
+                model: None,
+                texts: &texts,
+                truncate: Truncate::End,
+                input_type: None,
+            })
+            .await;
+        assert_eq!(vec![vec![1.0, 0.0], vec![0.0, 1.0]], response.unwrap());
+
+        // only the text that was not embedded yet is sent
+        let texts = [
+            "world".to_string(),
+            "again".to_string(),
+            "hello".to_string(),
+        ];
+        let response = client
+            .embed(&EmbedRequest {
+                model: None,
+                texts: &texts,
+                truncate: Truncate::End,
+                input_type: None,
+            })
+            .await;
+        assert_eq!(
+            vec![vec![0.0, 1.0], vec![0.5, 0.5], vec![1.0, 0.0]],
+            response.unwrap()
+        );
+
+        assert_eq!(
+            Some(CacheStats {
+                hits: 3,
+                misses: 4,
+                evictions: 0,
+                entries: 4,
+            }),
+            client.cache_stats()
+        );
+
+        // assert that mock endpoints were called once
+        mock_detokenize.assert_async().await;
+        mock_embed_batch.assert_async().await;
+        mock_embed_missing.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_embedding_store() {
+        // Create mock server
+        let mut mock_server = mockito::Server::new_async().await;
+        let mock_url = mock_server.url();
+
+        // Create mock
+        let mock = mock_server
+            .mock("POST", "/embed")
+            .match_body(mockito::Matcher::PartialJsonString(
+                r#"{"model":"embed-english-v3.0","texts":["hello","world"],"input_type":"search_document"}"#.to_string(),
+            ))
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"embeddings":[[1.0,0.0],[0.0,1.0]]}"#)
+            .expect(1)
+            .create_async()
+            .await;
+
+        let mock_again = mock_server
+            .mock("POST", "/embed")
+            .match_body(mockito::Matcher::PartialJsonString(
+                r#"{"model":"embed-english-v3.0","texts":["again"],"input_type":"search_document"}"#.to_string(),
+            ))
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"embeddings":[[0.5,0.5]]}"#)
+            .expect(1)
+            .create_async()
+            .await;
+
+        let mock_default_model = mock_server
+            .mock("POST", "/embed")
+            .match_body(mockito::Matcher::Json(serde_json::json!({
+                "texts": ["hello", "world"],
+                "truncate": "END",
+                "input_type": "search_document"
+            })))
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"embeddings":[[1.0,1.0],[1.0,1.0]]}"#)
+            .expect(2)
+            .create_async()
+            .await;
+
+        let path = std::env::temp_dir()
+            .join(format!("cohere-rust-{}", std::process::id()))
+            .join("embeddings.jsonl");
+        let _ = std::fs::remove_file(&path);
+
+        let texts = ["hello".to_string(), "world".to_string()];
+        let request = EmbedRequest {
+            model: Some(EmbedModel::EnglishV3),
+            texts: &texts,
+            truncate: Truncate::End,
+            input_type: Some(EmbedInputType::SearchDocument),
+        };
+
+        // the second client reads the embeddings written by the first one from the file
+        for _ in 0..2 {
+            let client = Cohere::builder()
+                .api_url(mock_url.clone())
+                .api_key("test-key")
+                .embedding_store(EmbeddingStore::open(&path).unwrap())
+                .build();
+
+            let response = client.embed(&request).await;
+            assert_eq!(vec![vec![1.0, 0.0], vec![0.0, 1.0]], response.unwrap());
+        }
+        assert_eq!(2, EmbeddingStore::open(&path).unwrap().len());
+
+        // without an explicit model the store is bypassed, as the default model may change
+        for _ in 0..2 {
+            let client = Cohere::builder()
+                .api_url(mock_url.clone())
+                .api_key("test-key")
+                .embedding_store(EmbeddingStore::open(&path).unwrap())
+                .build();
+
+            let response = client
+                .embed(&EmbedRequest {
+                    model: None,
+                    ..request.clone()
+                })
+                .await;
+            assert_eq!(vec![vec![1.0, 1.0], vec![1.0, 1.0]], response.unwrap());
+        }
+        assert_eq!(2, EmbeddingStore::open(&path).unwrap().len());
+
+        // an entry cut short by a crash is skipped, and does not swallow the next one
+        let mut file = std::fs::OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap();
+        std::io::Write::write_all(&mut file, br#"{"key":"partial","embed"#).unwrap();
+        drop(file);
+
+        let texts = ["again".to_string()];
+        let client = Cohere::builder()
+            .api_url(mock_url)
+            .api_key("test-key")
+            .embedding_store(EmbeddingStore::open(&path).unwrap())
+            .build();
+        let response = client
+            .embed(&EmbedRequest {
+                texts: &texts,
+                ..request
+            })
+            .await;
+        assert_eq!(vec![vec![0.5, 0.5]], response.unwrap());
+        assert_eq!(3, EmbeddingStore::open(&path).unwrap().len());
+        let _ = std::fs::remove_file(&path);
+
+        // assert that mock endpoints were called once
+        mock.assert_async().await;
+        mock_again.assert_async().await;
+        mock_default_model.assert_async().await;
+    }
+
+    struct ChunkedTransport {
+        chunks: Vec<&'static str>,
+    }
+
+    impl Transport for ChunkedTransport {
+        fn send(
+            &self,
+            _request: HttpRequest,
+        ) -> BoxFuture<'_, Result<HttpResponse, CohereApiError>> {
+            let chunks = self
+                .chunks
+                .iter()
+                .map(|chunk| chunk.as_bytes().into())
+                .collect();
+            Box::pin(async move {
+                Ok(HttpResponse::from_chunks(
+                    StatusCode::OK,
+                    HeaderMap::new(),
+                    chunks,
+                ))
+            })
+        }
+    }
+
+    #[test]
+    fn test_chat_stream_without_tokio() {
+        let client = Cohere::builder()
+            .api_key("test-key")
+            .transport(ChunkedTransport {
+                chunks: vec![
+                    "{\"is_finished\":false,\"event_type\":\"text-generation\",\"text\":\" Thomas\"}",
+                    "{\"is_finished\":true,\"event_type\":\"stream-end\",\"response\":{\"response_id\":\"feab94ed-789b-42f2-8f4f-c49d56d28734\",\"text\":\"Thomas\",\"generation_id\":\"0c9cb118-f841-4588-b835-f9a4fe2c572e\"},\"finish_reason\":\"COMPLETE\"}",
+                ],
+            })
+            .build();
+
+        let events = futures_executor::block_on(async {
+            let mut stream = client
+                .chat_stream(&ChatStreamRequest::from(ChatRequest {
+                    message: "who wrote the book where is my cheese?",
+                    ..Default::default()
+                }))
+                .await
+                .unwrap();
+
+            let mut events = Vec::new();
+            while let Some(event) = stream.recv().await {
+                events.push(event.unwrap());
+            }
+            events
+        });
+
+        assert_eq!(2, events.len());
+        assert_eq!(
+            StreamEvent::TextGeneration {
+                is_finished: false,
+                text: " Thomas".to_string(),
+            },
+            events[0]
+        );
+        assert!(matches!(events[1], StreamEvent::End { .. }));
+    }
+
+    #[test]
+    fn test_chat_stream_fragmented() {
+        let client = Cohere::builder()
+            .api_key("test-key")
+            .transport(ChunkedTransport {
+                chunks: vec![
+                    // an event split over chunks, then two events in one chunk
+                    "{\"is_finished\":false,\"event_type\":\"text-gen",
+                    "eration\",\"text\":\" Thomas\"}\n",
+                    "{\"is_finished\":false,\"event_type\":\"text-generation\",\"text\":\" P\"}\n{\"is_finished\":false,\"event_type\":\"text-generation\",\"text\":\".\"}\n",
+                    // server-sent events framing, with a final event not followed by a newline
+                    "event: text-generation\r\ndata: {\"is_finished\":false,\"event_type\":\"text-generation\",\"text\":\" Frank\"}\r\n\r\n",
+                    "data: {\"is_finished\":true,\"event_type\":\"stream-end\",\"response\":{\"response_id\":\"feab94ed-789b-42f2-8f4f-c49d56d28734\",\"text\":\"Thomas P. Frank\",\"generation_id\":\"0c9cb118-f841-4588-b835-f9a4fe2c572e\"},",
+                    "\"finish_reason\":\"COMPLETE\"}",
+                ],
+            })
+            .build();
+
+        let events = futures_executor::block_on(async {
+            let mut stream = client
+                .chat_stream(&ChatStreamRequest::from(ChatRequest {
+                    message: "who wrote the book where is my cheese?",
+                    ..Default::default()
+                }))
+                .await
+                .unwrap();
+
+            let mut events = Vec::new();
+            while let Some(event) = stream.recv().await {
+                events.push(event.unwrap());
+            }
+            events
+        });
+
+        assert_eq!(5, events.len());
+        let texts: Vec<&str> = events
+            .iter()
+            .filter_map(|event| match event {
+                StreamEvent::TextGeneration { text, .. } => Some(text.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(vec![" Thomas", " P", ".", " Frank"], texts);
+        assert!(matches!(events[4], StreamEvent::End { .. }));
+    }
+
+    #[test]
+    fn test_chat_stream_events() {
+        let client = Cohere::builder()
+            .api_key("test-key")
+            .transport(ChunkedTransport {
+                chunks: vec![
+                    "{\"is_finished\":false,\"event_type\":\"stream-start\",\"generation_id\":\"0c9cb118-f841-4588-b835-f9a4fe2c572e\"}\n",
+                    "{\"is_finished\":false,\"event_type\":\"search-queries-generation\",\"search_queries\":[{\"text\":\"where is my cheese author\",\"generation_id\":\"5e2ae3a8-1a8b-4d5e-9a8a-e4f0c2b7a9b1\"}]}\n",
+                    "{\"is_finished\":false,\"event_type\":\"search-results\",\"search_results\":[{\"search_query\":{\"text\":\"where is my cheese author\",\"generation_id\":\"5e2ae3a8-1a8b-4d5e-9a8a-e4f0c2b7a9b1\"},\"document_ids\":[\"doc_0\"],\"connector\":{\"id\":\"web-search\"}}],\"documents\":[{\"id\":\"doc_0\",\"title\":\"Who Moved My Cheese?\"}]}\n",
+                    "{\"is_finished\":false,\"event_type\":\"text-generation\",\"text\":\"Spencer Johnson.\"}\n",
+                    "{\"is_finished\":false,\"event_type\":\"citation-generation\",\"citations\":[{\"start\":0,\"end\":15,\"text\":\"Spencer Johnson\",\"document_ids\":[\"doc_0\"]}]}\n",
+                    "{\"is_finished\":false,\"event_type\":\"tool-calls-chunk\",\"tool_call_delta\":{\"index\":0,\"name\":\"lookup\"}}\n",
+                    "{\"is_finished\":false,\"event_type\":\"tool-calls-generation\",\"tool_calls\":[{\"name\":\"lookup\",\"parameters\":{\"title\":\"Who Moved My Cheese?\"}}]}\n",
+                    "{\"is_finished\":false,\"event_type\":\"debug\",\"prompt\":\"...\"}\n",
+                    "{\"is_finished\":true,\"event_type\":\"stream-end\",\"response\":{\"response_id\":\"feab94ed-789b-42f2-8f4f-c49d56d28734\",\"text\":\"Spencer Johnson.\",\"generation_id\":\"0c9cb118-f841-4588-b835-f9a4fe2c572e\"},\"finish_reason\":\"COMPLETE\"}\n",
+                ],
+            })
+            .build();
+
+        let events = futures_executor::block_on(async {
+            client
+                .chat_stream(&ChatStreamRequest::from(ChatRequest {
+                    message: "who wrote the book where is my cheese?",
+                    ..Default::default()
+                }))
+                .await
+                .unwrap()
+                .try_collect::<Vec<_>>()
+                .await
+                .unwrap()
+        });
+
+        let event_types: Vec<&str> = events.iter().map(StreamEvent::event_type).collect();
+        assert_eq!(
+            vec![
+                "stream-start",
+                "search-queries-generation",
+                "search-results",
+                "text-generation",
+                "citation-generation",
+                "tool-calls-chunk",
+                "tool-calls-generation",
+                "debug",
+                "stream-end",
+            ],
+            event_types
+        );
+        assert_eq!(
+            StreamEvent::CitationGeneration {
+                is_finished: false,
+                citations: vec![ChatCitation {
+                    start: 0,
+                    end: 15,
+                    text: "Spencer Johnson".to_string(),
+                    document_ids: vec!["doc_0".to_string()],
+                }],
+            },
+            events[4]
+        );
+        match &events[2] {
+            StreamEvent::SearchResults {
+                search_results: Some(search_results),
+                documents: Some(documents),
+                ..
+            } => {
+                assert_eq!("web-search", search_results[0].connector.id);
+                assert_eq!(Some("Who Moved My Cheese?"), documents[0].title.as_deref());
+            }
+            event => panic!("unexpected event: {event:?}"),
+        }
+        match &events[7] {
+            StreamEvent::Unknown { raw, .. } => assert_eq!("...", raw["prompt"]),
+            event => panic!("unexpected event: {event:?}"),
+        }
+    }
+
+    #[test]
+    fn test_chat_stream_aggregator() {
+        let client = Cohere::builder()
+            .api_key("test-key")
+            .transport(ChunkedTransport {
+                chunks: vec![
+                    "{\"is_finished\":false,\"event_type\":\"stream-start\",\"generation_id\":\"0c9cb118-f841-4588-b835-f9a4fe2c572e\"}\n",
+                    "{\"is_finished\":false,\"event_type\":\"text-generation\",\"text\":\"Spencer\"}\n",
+                    "{\"is_finished\":false,\"event_type\":\"text-generation\",\"text\":\" Johnson.\"}\n",
+                    "{\"is_finished\":false,\"event_type\":\"citation-generation\",\"citations\":[{\"start\":0,\"end\":15,\"text\":\"Spencer Johnson\",\"document_ids\":[\"doc_0\"]}]}\n",
+                    "{\"is_finished\":true,\"event_type\":\"stream-end\",\"response\":{\"response_id\":\"feab94ed-789b-42f2-8f4f-c49d56d28734\",\"text\":\"Spencer Johnson.\",\"generation_id\":\"0c9cb118-f841-4588-b835-f9a4fe2c572e\",\"meta\":{\"billed_units\":{\"input_tokens\":71,\"output_tokens\":4},\"tokens\":{\"input_tokens\":120,\"output_tokens\":4}}},\"finish_reason\":\"COMPLETE\"}\n",
+                ],
+            })
+            .build();
+
+        let response = futures_executor::block_on(async {
+            client
+                .chat_stream(&ChatStreamRequest::from(ChatRequest {
+                    message: "who wrote the book where is my cheese?",
+                    ..Default::default()
+                }))
+                .await
+                .unwrap()
+                .response()
+                .await
+                .unwrap()
+        });
+
+        // the response of the same chat without streaming
+        let expected: ChatResponse = serde_json::from_str(
+            r#"{"response_id":"feab94ed-789b-42f2-8f4f-c49d56d28734","text":"Spencer Johnson.","generation_id":"0c9cb118-f841-4588-b835-f9a4fe2c572e","finish_reason":"COMPLETE","citations":[{"start":0,"end":15,"text":"Spencer Johnson","document_ids":["doc_0"]}],"meta":{"billed_units":{"input_tokens":71,"output_tokens":4},"tokens":{"input_tokens":120,"output_tokens":4}}}"#,
+        )
+        .unwrap();
+        assert_eq!(expected, response);
+
+        // a stream cut before its end has no response
+        let mut aggregator = ChatStreamAggregator::new();
+        aggregator.push(&StreamEvent::TextGeneration {
+            is_finished: false,
+            text: "Spencer".to_string(),
+        });
+        assert_eq!("Spencer", aggregator.text());
+        assert!(matches!(
+            aggregator.finish(),
+            Err(CohereStreamError::Incomplete)
+        ));
+    }
+
+    #[test]
+    fn test_chat_stream_metrics() {
+        let metrics = Arc::new(InMemoryMetrics::new());
+        let client = Cohere::builder()
+            .api_key("test-key")
+            .metrics(metrics.clone())
+            .transport(ChunkedTransport {
+                chunks: vec![
+                    "{\"is_finished\":false,\"event_type\":\"text-generation\",\"text\":\"Spencer Johnson.\"}\n",
+                    "{\"is_finished\":true,\"event_type\":\"stream-end\",\"response\":{\"response_id\":\"feab94ed-789b-42f2-8f4f-c49d56d28734\",\"text\":\"Spencer Johnson.\",\"generation_id\":\"0c9cb118-f841-4588-b835-f9a4fe2c572e\",\"meta\":{\"billed_units\":{\"input_tokens\":71,\"output_tokens\":4}}},\"finish_reason\":\"COMPLETE\"}\n",
+                ],
+            })
+            .build();
+
+        futures_executor::block_on(async {
+            let stream = client
+                .chat_stream(&ChatStreamRequest::from(ChatRequest {
+                    message: "who wrote the book where is my cheese?",
+                    ..Default::default()
+                }))
+                .await
+                .unwrap();
+            // the call is reported once the stream has ended
+            assert_eq!(None, metrics.route("chat"));
+            stream.response().await.unwrap()
+        });
+
+        let chat = metrics.route("chat").unwrap();
+        assert_eq!(1, chat.requests);
+        assert_eq!(71.0, chat.input_tokens);
+        assert_eq!(4.0, chat.output_tokens);
+    }
+
+    /// Sends one event, then keeps the body open until it is dropped.
+    struct OpenBodyTransport {
+        body: Arc<()>,
+    }
+
+    impl Transport for OpenBodyTransport {
+        fn send(
+            &self,
+            _request: HttpRequest,
+        ) -> BoxFuture<'_, Result<HttpResponse, CohereApiError>> {
+            let body = self.body.clone();
+            Box::pin(async move {
+                let event = Bytes::from_static(
+                    b"{\"is_finished\":false,\"event_type\":\"text-generation\",\"text\":\" Thomas\"}\n",
+                );
+                Ok(HttpResponse {
+                    status: StatusCode::OK,
+                    headers: HeaderMap::new(),
+                    body: stream::iter([Ok(event)])
+                        .chain(stream::pending().map(move |()| {
+                            let _ = &body;
+                            Ok(Bytes::new())
+                        }))
+                        .boxed(),
+                })
+            })
+        }
+    }
+
+    #[test]
+    fn test_chat_stream_drop() {
+        let body = Arc::new(());
+        let client = Cohere::builder()
+            .api_key("test-key")
+            .transport(OpenBodyTransport { body: body.clone() })
+            .build();
+
+        let texts = futures_executor::block_on(async {
+            let stream = client
+                .chat_stream(&ChatStreamRequest::from(ChatRequest {
+                    message: "who wrote the book where is my cheese?",
+                    ..Default::default()
+                }))
+                .await
+                .unwrap();
+            // held by the test, the transport and the response body
+            assert_eq!(3, Arc::strong_count(&body));
+
+            stream
+                .map_ok(|event| match event {
+                    StreamEvent::TextGeneration { text, .. } => text,
+                    _ => String::new(),
+                })
+                .take(1)
+                .try_collect::<Vec<_>>()
+                .await
+                .unwrap()
+        });
+
+        assert_eq!(vec![" Thomas".to_string()], texts);
+        // dropping the stream dropped the response body
+        assert_eq!(2, Arc::strong_count(&body));
+    }
+
+    #[tokio::test]
+    async fn test_chat_documents() {
+        // Create mock server
+        let mut mock_server = mockito::Server::new_async().await;
+        let mock_url = mock_server.url();
+
+        // Create a mock
+        let mock_endpoint = mock_server
+            .mock("POST", "/chat")
+            .match_body(mockito::Matcher::PartialJsonString(
+                r#"{"documents":[{"id":"doc_0","title":"Who Moved My Cheese?","snippet":"A book by Spencer Johnson.","url":"https://example.com/cheese"}],"citation_quality":"fast"}"#.to_string(),
+            ))
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"response_id":"feab94ed-789b-42f2-8f4f-c49d56d28734","text":"Spencer Johnson.","generation_id":"0c9cb118-f841-4588-b835-f9a4fe2c572e","finish_reason":"COMPLETE","citations":[{"start":0,"end":15,"text":"Spencer Johnson","document_ids":["doc_0"]}],"documents":[{"id":"doc_0","title":"Who Moved My Cheese?","snippet":"A book by Spencer Johnson.","url":"https://example.com/cheese"}]}"#)
+            .create_async()
+            .await;
+
+        let client = Cohere::new(mock_url, "test-key");
+
+        let documents = vec![ChatDocument {
+            id: Some("doc_0".to_string()),
+            title: Some("Who Moved My Cheese?".to_string()),
+            snippet: Some("A book by Spencer Johnson.".to_string()),
+            fields: HashMap::from([("url".to_string(), "https://example.com/cheese".into())]),
+        }];
+        let response = client
+            .chat(&ChatRequest {
+                message: "who wrote the book where is my cheese?",
+                documents: Some(&documents),
+                citation_quality: Some(CitationQuality::Fast),
+                ..Default::default()
+            })
+            .await;
+
+        // assert that mock endpoint was called
+        mock_endpoint.assert_async().await;
+
+        let response = response.unwrap();
+        assert_eq!(Some(&documents), response.documents.as_ref());
+        let citations = response.citations.as_deref().unwrap();
+        assert_eq!("Spencer Johnson", citations[0].text);
+        assert_eq!(vec![&documents[0]], response.cited_documents(&citations[0]));
+    }
+
+    #[tokio::test]
+    async fn test_chat_stream_error() {
+        // Create mock server
+        let mut mock_server = mockito::Server::new_async().await;
+        let mock_url = mock_server.url();
+
+        // Create a mock
+        let mock_endpoint = mock_server
+            .mock("POST", "/chat")
+            .with_status(400)
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"message":"invalid request: message must not be empty"}"#)
+            .create_async()
+            .await;
+
+        let client = Cohere::new(mock_url, "test-key");
+
+        let response = client
+            .chat_stream(&ChatStreamRequest::from(ChatRequest {
+                message: "who wrote the book where is my cheese?",
+                ..Default::default()
+            }))
+            .await;
+
+        // assert that mock endpoint was called
+        mock_endpoint.assert_async().await;
+
+        match response {
+            Err(CohereApiError::BadRequest(details)) => assert_eq!(
+                "invalid request: message must not be empty",
+                details.message
+            ),
+            other => panic!("unexpected response: {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_record_and_replay() {
+        // Create mock server
+        let mut mock_server = mockito::Server::new_async().await;
+        let mock_url = mock_server.url();
+
+        let mock_chat_stream = [
+            "{\"is_finished\":false,\"event_type\":\"text-generation\",\"text\":\" Thomas\"}",
+            "{\"is_finished\":true,\"event_type\":\"stream-end\",\"response\":{\"response_id\":\"feab94ed-789b-42f2-8f4f-c49d56d28734\",\"text\":\"Thomas\",\"generation_id\":\"0c9cb118-f841-4588-b835-f9a4fe2c572e\"},\"finish_reason\":\"COMPLETE\"}",
+        ];
+
+        // Create mocks
+        let mock_detokenize = mock_server
+            .mock("POST", "/detokenize")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"text":"detokenized! :D"}"#)
+            .create_async()
+            .await;
+        let mock_chat = mock_server
+            .mock("POST", "/chat")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_chunked_body(move |w| {
+                for chunk in mock_chat_stream.iter() {
+                    w.write_all(chunk.as_bytes()).unwrap();
+                }
+                Ok(())
+            })
+            .create_async()
+            .await;
+
+        let cassette =
+            std::env::temp_dir().join(format!("cohere-rust-cassette-{}.json", std::process::id()));
+
+        let detokenize_request = DetokenizeRequest {
+            tokens: &[10002, 1706, 1722, 5169, 4328],
+            model: None,
+        };
+        let chat_request = ChatStreamRequest::from(ChatRequest {
+            message: "who wrote the book where is my cheese?",
+            ..Default::default()
+        });
+
+        let recording = Cohere::builder()
+            .api_url(mock_url)
+            .api_key("secret-key")
+            .record_to(&cassette)
+            .build();
+
+        assert!(recording.detokenize(&detokenize_request).await.is_ok());
+        let mut stream = recording.chat_stream(&chat_request).await.unwrap();
+        let mut recorded_events = Vec::new();
+        while let Some(event) = stream.recv().await {
+            recorded_events.push(event.unwrap());
+        }
+        drop(stream);
+
+        // assert that mock endpoints were called
+        mock_detokenize.assert_async().await;
+        mock_chat.assert_async().await;
+
+        let content = std::fs::read_to_string(&cassette).unwrap();
+        assert!(!content.contains("secret-key"));
+
+        // replay against an address that cannot be reached
+        let replaying = Cohere::builder()
+            .api_url("http://127.0.0.1:1")
+            .api_key("other-key")
+            .replay_from(&cassette)
+            .build();
+
+        assert_eq!(
+            "detokenized! :D".to_string(),
+            replaying.detokenize(&detokenize_request).await.unwrap()
+        );
+        let mut stream = replaying.chat_stream(&chat_request).await.unwrap();
+        let mut replayed_events = Vec::new();
+        while let Some(event) = stream.recv().await {
+            replayed_events.push(event.unwrap());
+        }
+        assert_eq!(recorded_events, replayed_events);
+
+        // every interaction is served once
+        assert!(matches!(
+            replaying.detokenize(&detokenize_request).await,
+            Err(CohereApiError::Transport(_))
+        ));
+
+        std::fs::remove_file(&cassette).unwrap();
+    }
 }
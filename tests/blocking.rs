@@ -0,0 +1,70 @@
+#![cfg(feature = "blocking")]
+
+use cohere_rust::{
+    api::{
+        chat::{ChatRequest, ChatStreamRequest, StreamEvent},
+        detokenize::DetokenizeRequest,
+    },
+    blocking::Cohere,
+};
+
+#[test]
+fn test_blocking_detokenize() {
+    // Create mock server
+    let mut mock_server = mockito::Server::new();
+    let mock_url = mock_server.url();
+
+    // Create a mock
+    let mock_endpoint = mock_server
+        .mock("POST", "/detokenize")
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(r#"{"text":"detokenized! :D"}"#)
+        .create();
+
+    let client = Cohere::new(mock_url, "test-key");
+
+    let response = client.detokenize(&DetokenizeRequest {
+        tokens: &[10002, 1706, 1722, 5169, 4328],
+        model: None,
+    });
+
+    // assert that mock endpoint was called
+    mock_endpoint.assert();
+
+    assert_eq!("detokenized! :D".to_string(), response.unwrap());
+}
+
+#[test]
+fn test_blocking_chat_stream() {
+    // Create mock server
+    let mut mock_server = mockito::Server::new();
+    let mock_url = mock_server.url();
+
+    // Create a mock
+    let mock_endpoint = mock_server
+        .mock("POST", "/chat")
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(
+            "{\"is_finished\":true,\"event_type\":\"stream-end\",\"response\":{\"response_id\":\"feab94ed-789b-42f2-8f4f-c49d56d28734\",\"text\":\"Thomas P. Frank.\",\"generation_id\":\"0c9cb118-f841-4588-b835-f9a4fe2c572e\"},\"finish_reason\":\"COMPLETE\"}",
+        )
+        .create();
+
+    let client = Cohere::new(mock_url, "test-key");
+
+    let events = client
+        .chat_stream(&ChatStreamRequest::from(ChatRequest {
+            message: "who wrote the book where is my cheese?",
+            ..Default::default()
+        }))
+        .unwrap()
+        .collect::<Result<Vec<_>, _>>();
+
+    // assert that mock endpoint was called
+    mock_endpoint.assert();
+
+    let events = events.unwrap();
+    assert_eq!(1, events.len());
+    assert!(matches!(events[0], StreamEvent::End { .. }));
+}
@@ -0,0 +1,140 @@
+#![cfg(feature = "tracing")]
+
+use std::{
+    io,
+    sync::{Arc, Mutex},
+};
+
+use cohere_rust::{
+    api::{
+        chat::{ChatRequest, ChatStreamRequest},
+        generate::GenerateRequest,
+        GenerateModel,
+    },
+    Cohere,
+};
+use tracing_subscriber::{filter::LevelFilter, fmt::format::FmtSpan, util::SubscriberInitExt};
+
+#[derive(Clone, Default)]
+struct Output(Arc<Mutex<Vec<u8>>>);
+
+impl io::Write for Output {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn test_tracing_span() {
+    let output = Output::default();
+    let writer = output.clone();
+    let _subscriber = tracing_subscriber::fmt()
+        .with_max_level(LevelFilter::DEBUG)
+        .with_span_events(FmtSpan::CLOSE)
+        .with_writer(move || writer.clone())
+        .set_default();
+
+    // Create mock server
+    let mut mock_server = mockito::Server::new_async().await;
+    let mock_url = mock_server.url();
+
+    // Create a mock
+    let mock_endpoint = mock_server
+        .mock("POST", "/generate")
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(
+            r#"{
+                "id": "65e5ecf2-0872-45d2-b15c-f59647273e97",
+                "generations": [{ "id": "ef047b8a", "text": " Silicon Valley" }],
+                "meta": { "billed_units": { "input_tokens": 9, "output_tokens": 20 } }
+            }"#,
+        )
+        .create_async()
+        .await;
+
+    let client = Cohere::new(mock_url, "test-key");
+
+    let response = client
+        .generate(&GenerateRequest {
+            model: Some(GenerateModel::CommandR),
+            prompt: "Once upon a time in a magical land called",
+            ..Default::default()
+        })
+        .await;
+
+    // assert that mock endpoint was called
+    mock_endpoint.assert_async().await;
+    assert!(response.is_ok());
+
+    let output = String::from_utf8(output.0.lock().unwrap().clone()).unwrap();
+    for field in [
+        "cohere.request{",
+        "otel.name=text_completion command-r",
+        "gen_ai.operation.name=\"text_completion\"",
+        "gen_ai.request.model=\"command-r\"",
+        "http.response.status_code=200",
+        "cohere.attempts=1",
+        "gen_ai.response.id=\"65e5ecf2-0872-45d2-b15c-f59647273e97\"",
+        "gen_ai.usage.input_tokens=9",
+        "gen_ai.usage.output_tokens=20",
+        "cohere.latency_ms=",
+    ] {
+        assert!(output.contains(field), "missing `{field}` in {output}");
+    }
+}
+
+#[tokio::test]
+async fn test_tracing_chat_stream_usage() {
+    let output = Output::default();
+    let writer = output.clone();
+    let _subscriber = tracing_subscriber::fmt()
+        .with_max_level(LevelFilter::DEBUG)
+        .with_span_events(FmtSpan::CLOSE)
+        .with_writer(move || writer.clone())
+        .set_default();
+
+    // Create mock server
+    let mut mock_server = mockito::Server::new_async().await;
+    let mock_url = mock_server.url();
+
+    // Create a mock
+    let mock_endpoint = mock_server
+        .mock("POST", "/chat")
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(
+            "{\"is_finished\":true,\"event_type\":\"stream-end\",\"response\":{\"response_id\":\"feab94ed-789b-42f2-8f4f-c49d56d28734\",\"text\":\"Spencer Johnson.\",\"generation_id\":\"0c9cb118-f841-4588-b835-f9a4fe2c572e\",\"meta\":{\"billed_units\":{\"input_tokens\":71,\"output_tokens\":4}}},\"finish_reason\":\"COMPLETE\"}\n",
+        )
+        .create_async()
+        .await;
+
+    let client = Cohere::new(mock_url, "test-key");
+
+    let response = client
+        .chat_stream(&ChatStreamRequest::from(ChatRequest {
+            message: "who wrote the book where is my cheese?",
+            ..Default::default()
+        }))
+        .await
+        .unwrap()
+        .response()
+        .await;
+
+    // assert that mock endpoint was called
+    mock_endpoint.assert_async().await;
+    assert!(response.is_ok());
+
+    let output = String::from_utf8(output.0.lock().unwrap().clone()).unwrap();
+    for field in [
+        "gen_ai.response.finish_reasons=\"COMPLETE\"",
+        "gen_ai.usage.input_tokens=71",
+        "gen_ai.usage.output_tokens=4",
+    ] {
+        assert!(output.contains(field), "missing `{field}` in {output}");
+    }
+}